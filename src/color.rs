@@ -0,0 +1,47 @@
+use std::env;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum ColorChoice {
+  #[default]
+  Auto,
+  Always,
+  Never,
+}
+
+impl ColorChoice {
+  pub(crate) fn enabled(self, is_terminal: bool) -> bool {
+    match self {
+      ColorChoice::Always => true,
+      ColorChoice::Never => false,
+      ColorChoice::Auto => {
+        let set = |name: &str| {
+          env::var_os(name).is_some_and(|v| !v.is_empty() && v != "0")
+        };
+        if env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty()) {
+          false
+        } else if set("CLICOLOR_FORCE") {
+          true
+        } else {
+          is_terminal
+        }
+      }
+    }
+  }
+}
+
+pub(crate) const BOLD: &str = "\x1b[1m";
+pub(crate) const GREEN: &str = "\x1b[32m";
+pub(crate) const RED: &str = "\x1b[31m";
+const RESET: &str = "\x1b[0m";
+
+pub(crate) fn paint(
+  enabled: bool,
+  style: &str,
+  text: &str,
+) -> String {
+  if enabled && !text.is_empty() {
+    format!("{}{}{}", style, text, RESET)
+  } else {
+    text.to_string()
+  }
+}
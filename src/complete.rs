@@ -0,0 +1,550 @@
+use crate::opt::{Arg, Opt, Optz};
+
+enum Json {
+  Bool(bool),
+  Str(String),
+  Arr(Vec<Json>),
+  Obj(Vec<(String, Json)>),
+}
+
+impl Json {
+  fn str(s: &str) -> Json {
+    Json::Str(s.to_string())
+  }
+
+  fn write(&self, out: &mut String, indent: usize) {
+    let pad = "  ".repeat(indent + 1);
+    match self {
+      Json::Bool(b) => {
+        out.push_str(if *b { "true" } else { "false" })
+      }
+      Json::Str(s) => write_str(out, s),
+      Json::Arr(items) if items.is_empty() => out.push_str("[]"),
+      Json::Arr(items) => {
+        out.push_str("[\n");
+        for (i, item) in items.iter().enumerate() {
+          out.push_str(&pad);
+          item.write(out, indent + 1);
+          out.push_str(if i + 1 < items.len() {
+            ",\n"
+          } else {
+            "\n"
+          });
+        }
+        out.push_str(&"  ".repeat(indent));
+        out.push(']');
+      }
+      Json::Obj(fields) if fields.is_empty() => out.push_str("{}"),
+      Json::Obj(fields) => {
+        out.push_str("{\n");
+        for (i, (key, value)) in fields.iter().enumerate() {
+          out.push_str(&pad);
+          write_str(out, key);
+          out.push_str(": ");
+          value.write(out, indent + 1);
+          out.push_str(if i + 1 < fields.len() {
+            ",\n"
+          } else {
+            "\n"
+          });
+        }
+        out.push_str(&"  ".repeat(indent));
+        out.push('}');
+      }
+    }
+  }
+}
+
+fn write_str(out: &mut String, s: &str) {
+  out.push('"');
+  for c in s.chars() {
+    match c {
+      '"' => out.push_str("\\\""),
+      '\\' => out.push_str("\\\\"),
+      '\n' => out.push_str("\\n"),
+      '\r' => out.push_str("\\r"),
+      '\t' => out.push_str("\\t"),
+      c if (c as u32) < 0x20 => {
+        out.push_str(&format!("\\u{:04x}", c as u32))
+      }
+      c => out.push(c),
+    }
+  }
+  out.push('"');
+}
+
+fn render(json: Json) -> String {
+  let mut out = String::new();
+  json.write(&mut out, 0);
+  out.push('\n');
+  out
+}
+
+fn names(opt: &Opt) -> Vec<String> {
+  let mut names = vec![opt.long.clone()];
+  names.extend(opt.short.clone());
+  names.extend(
+    opt
+      .aliases
+      .iter()
+      .filter(|(_, visible)| *visible)
+      .map(|(alias, _)| alias.clone()),
+  );
+  names
+}
+
+fn description(optz: &Optz, opt: &Opt) -> String {
+  let mut hints = opt
+    .requires
+    .iter()
+    .map(|required| format!("(requires --{})", required))
+    .collect::<Vec<_>>();
+  hints.extend(
+    opt
+      .conflicts_with
+      .iter()
+      .map(|conflict| format!("(conflicts with --{})", conflict)),
+  );
+  hints.extend(
+    optz
+      .groups
+      .iter()
+      .filter(|group| group.args.contains(&opt.name))
+      .map(|group| format!("(group '{}')", group.name)),
+  );
+  let mut desc = opt.description.clone().unwrap_or_default();
+  for hint in hints {
+    if !desc.is_empty() {
+      desc.push(' ');
+    }
+    desc.push_str(&hint);
+  }
+  desc
+}
+
+// Options that cannot be combined with `opt`, either directly or as
+// another member of an exclusive group.
+fn exclusive_on(optz: &Optz, opt: &Opt) -> Vec<String> {
+  let mut names = Vec::new();
+  let groups = optz.groups.iter().filter(|group| {
+    group.exclusive && group.args.contains(&opt.name)
+  });
+  let others = groups.flat_map(|group| group.args.iter());
+  for name in opt.conflicts_with.iter().chain(others) {
+    let name = format!("--{}", name);
+    if name != opt.long && !names.contains(&name) {
+      names.push(name);
+    }
+  }
+  names
+}
+
+pub fn fig(optz: &Optz) -> String {
+  let mut optz = optz.clone();
+  optz.prepare();
+  render(fig_spec(&optz))
+}
+
+fn fig_spec(optz: &Optz) -> Json {
+  let options = optz
+    .options
+    .iter()
+    .filter(|opt| !matches!(opt.arg, Arg::Positional) && !opt.hidden)
+    .map(|opt| {
+      let mut fields = vec![
+        (
+          "name".to_string(),
+          Json::Arr(
+            names(opt).iter().map(|n| Json::str(n)).collect(),
+          ),
+        ),
+        (
+          "description".to_string(),
+          Json::Str(description(optz, opt)),
+        ),
+      ];
+      if let Arg::Arg = opt.arg {
+        let mut args =
+          vec![("name".to_string(), Json::str(&opt.name))];
+        if !opt.possible_values.is_empty() {
+          args.push((
+            "suggestions".to_string(),
+            Json::Arr(
+              opt
+                .possible_values
+                .iter()
+                .map(|v| Json::str(v))
+                .collect(),
+            ),
+          ));
+        }
+        fields.push(("args".to_string(), Json::Obj(args)));
+      }
+      if opt.multiple {
+        fields.push(("isRepeatable".to_string(), Json::Bool(true)));
+      }
+      if !opt.requires.is_empty() {
+        let names =
+          opt.requires.iter().map(|name| format!("--{}", name));
+        fields.push((
+          "dependsOn".to_string(),
+          Json::Arr(names.map(|name| Json::str(&name)).collect()),
+        ));
+      }
+      let exclusive = exclusive_on(optz, opt);
+      if !exclusive.is_empty() {
+        fields.push((
+          "exclusiveOn".to_string(),
+          Json::Arr(
+            exclusive.iter().map(|name| Json::str(name)).collect(),
+          ),
+        ));
+      }
+      if opt.deprecated.is_some() {
+        fields.push(("deprecated".to_string(), Json::Bool(true)));
+      }
+      Json::Obj(fields)
+    })
+    .collect();
+
+  let name = if optz.aliases.is_empty() {
+    Json::str(&optz.name)
+  } else {
+    let names = std::iter::once(&optz.name).chain(&optz.aliases);
+    Json::Arr(names.map(|name| Json::str(name)).collect())
+  };
+  let mut spec = vec![("name".to_string(), name)];
+  if let Some(desc) = &optz.description {
+    spec.push(("description".to_string(), Json::str(desc)));
+  }
+  spec.push(("options".to_string(), Json::Arr(options)));
+  let subcommands = visible_subcommands(optz)
+    .map(|sub| fig_spec(&sub))
+    .collect::<Vec<_>>();
+  if !subcommands.is_empty() {
+    spec.push(("subcommands".to_string(), Json::Arr(subcommands)));
+  }
+  let positionals = optz
+    .positionals()
+    .into_iter()
+    .filter(|opt| !opt.hidden)
+    .map(fig_arg)
+    .collect::<Vec<_>>();
+  let args = if positionals.is_empty() {
+    Json::Obj(vec![
+      ("name".to_string(), Json::str("args")),
+      ("isVariadic".to_string(), Json::Bool(true)),
+      ("isOptional".to_string(), Json::Bool(true)),
+    ])
+  } else {
+    Json::Arr(positionals)
+  };
+  spec.push(("args".to_string(), args));
+  Json::Obj(spec)
+}
+
+fn fig_arg(opt: &Opt) -> Json {
+  let mut arg = vec![("name".to_string(), Json::str(&opt.name))];
+  if let Some(desc) = &opt.description {
+    arg.push(("description".to_string(), Json::str(desc)));
+  }
+  if !opt.possible_values.is_empty() {
+    let values = opt.possible_values.iter().map(|v| Json::str(v));
+    arg.push(("suggestions".to_string(), Json::Arr(values.collect())));
+  }
+  if opt.many.is_some() {
+    arg.push(("isVariadic".to_string(), Json::Bool(true)));
+  }
+  let min = opt.many.map_or(0, |(min, _)| min);
+  if !opt.required && min == 0 {
+    arg.push(("isOptional".to_string(), Json::Bool(true)));
+  }
+  Json::Obj(arg)
+}
+
+fn visible_subcommands(optz: &Optz) -> impl Iterator<Item = Optz> {
+  optz
+    .subcommands
+    .iter()
+    .filter(|sub| !sub.hidden)
+    .map(|sub| optz.prepared_subcommand(sub))
+}
+
+pub fn carapace(optz: &Optz) -> String {
+  let mut optz = optz.clone();
+  optz.prepare();
+  render(Json::Obj(carapace_spec(&optz)))
+}
+
+fn carapace_spec(optz: &Optz) -> Vec<(String, Json)> {
+  let mut flags = Vec::new();
+  let mut values = Vec::new();
+  for opt in &optz.options {
+    if matches!(opt.arg, Arg::Positional) || opt.hidden {
+      continue;
+    }
+    let mut suffix = String::new();
+    if let Arg::Arg = opt.arg {
+      suffix.push('=');
+    }
+    if opt.multiple {
+      suffix.push('*');
+    }
+    // Carapace keys take at most one short and one long name, short
+    // first; visible aliases get entries of their own.
+    let mut keys = vec![match &opt.short {
+      Some(short) => format!("{}, {}", short, opt.long),
+      None => opt.long.clone(),
+    }];
+    keys.extend(
+      opt
+        .aliases
+        .iter()
+        .filter(|(_, visible)| *visible)
+        .map(|(alias, _)| alias.clone()),
+    );
+    for key in keys {
+      flags.push((key + &suffix, Json::Str(description(optz, opt))));
+    }
+    if !opt.possible_values.is_empty() {
+      values.push((
+        opt.name.clone(),
+        Json::Arr(
+          opt.possible_values.iter().map(|v| Json::str(v)).collect(),
+        ),
+      ));
+    }
+  }
+
+  let mut spec = vec![("name".to_string(), Json::str(&optz.name))];
+  if !optz.aliases.is_empty() {
+    let aliases = optz.aliases.iter().map(|a| Json::str(a));
+    spec.push(("aliases".to_string(), Json::Arr(aliases.collect())));
+  }
+  if let Some(desc) = &optz.description {
+    spec.push(("description".to_string(), Json::str(desc)));
+  }
+  spec.push(("flags".to_string(), Json::Obj(flags)));
+  if !values.is_empty() {
+    spec.push((
+      "completion".to_string(),
+      Json::Obj(vec![("flag".to_string(), Json::Obj(values))]),
+    ));
+  }
+  let commands = visible_subcommands(optz)
+    .map(|sub| Json::Obj(carapace_spec(&sub)))
+    .collect::<Vec<_>>();
+  if !commands.is_empty() {
+    spec.push(("commands".to_string(), Json::Arr(commands)));
+  }
+  spec
+}
+
+pub fn zsh(optz: &Optz) -> String {
+  let mut optz = optz.clone();
+  optz.prepare();
+  let mut out = format!("#compdef {}\n", optz.name);
+  zsh_function(&optz, &format!("_{}", optz.name), &mut out);
+  out.push_str(&format!("\n_{} \"$@\"\n", optz.name));
+  out
+}
+
+fn zsh_function(optz: &Optz, function: &str, out: &mut String) {
+  let subcommands = visible_subcommands(optz).collect::<Vec<_>>();
+  let mut specs = optz
+    .options
+    .iter()
+    .filter(|opt| !matches!(opt.arg, Arg::Positional) && !opt.hidden)
+    .map(|opt| zsh_option(optz, opt))
+    .collect::<Vec<_>>();
+  if !subcommands.is_empty() {
+    specs.push(format!("'1: :{}_commands'", function));
+    specs.push("'*::arg:->args'".to_string());
+  }
+  out.push_str(&format!("\n{}() {{\n", function));
+  if !subcommands.is_empty() {
+    out.push_str("  local line state\n");
+  }
+  out.push_str("  _arguments -s -S");
+  for spec in specs {
+    out.push_str(&format!(" \\\n    {}", spec));
+  }
+  out.push('\n');
+  if !subcommands.is_empty() {
+    out.push_str("  case $state in\n    args)\n");
+    out.push_str("      case $line[1] in\n");
+    for sub in &subcommands {
+      let names = std::iter::once(&sub.name).chain(&sub.aliases);
+      out.push_str(&format!(
+        "        {}) {}_{} ;;\n",
+        names.map(|n| zsh_quote(n)).collect::<Vec<_>>().join("|"),
+        function,
+        sub.name
+      ));
+    }
+    out.push_str("      esac\n      ;;\n  esac\n");
+  }
+  out.push_str("}\n");
+  if subcommands.is_empty() {
+    return;
+  }
+  out.push_str(&format!(
+    "\n{}_commands() {{\n  local commands=(\n",
+    function
+  ));
+  for sub in &subcommands {
+    let desc = sub.description.clone().unwrap_or_default();
+    for name in std::iter::once(&sub.name).chain(&sub.aliases) {
+      let entry = format!("{}:{}", name.replace(':', "\\:"), desc);
+      out.push_str(&format!("    {}\n", zsh_quote(&entry)));
+    }
+  }
+  out.push_str("  )\n  _describe 'command' commands\n}\n");
+  for sub in &subcommands {
+    zsh_function(sub, &format!("{}_{}", function, sub.name), out);
+  }
+}
+
+// One `_arguments` spec: exclusion list, names, description in
+// brackets and, for options that take a value, its completions.
+fn zsh_option(optz: &Optz, opt: &Opt) -> String {
+  let names = names(opt);
+  let mut exclusive = exclusive_on(optz, opt);
+  if !opt.multiple {
+    exclusive.splice(0..0, names.iter().cloned());
+  }
+  let mut prefix = String::new();
+  if !exclusive.is_empty() {
+    prefix.push_str(&format!("({})", exclusive.join(" ")));
+  }
+  if opt.multiple {
+    prefix.push('*');
+  }
+  let suffix = |name: &str| match opt.arg {
+    Arg::Arg if name.starts_with("--") => format!("{}=", name),
+    Arg::Arg => format!("{}+", name),
+    _ => name.to_string(),
+  };
+  let desc = description(optz, opt)
+    .replace('\\', "\\\\")
+    .replace('[', "\\[")
+    .replace(']', "\\]");
+  let mut rest = format!("[{}]", desc);
+  if let Arg::Arg = opt.arg {
+    let values = opt
+      .possible_values
+      .iter()
+      .map(|v| v.replace(' ', "\\ "))
+      .collect::<Vec<_>>();
+    rest.push_str(&format!(":{}:", opt.name));
+    if !values.is_empty() {
+      rest.push_str(&format!("({})", values.join(" ")));
+    }
+  }
+  match names.as_slice() {
+    [name] => zsh_quote(&format!("{}{}{}", prefix, suffix(name), rest)),
+    _ => format!(
+      "{}{{{}}}{}",
+      zsh_quote(&prefix),
+      names.iter().map(|n| suffix(n)).collect::<Vec<_>>().join(","),
+      zsh_quote(&rest)
+    ),
+  }
+}
+
+fn zsh_quote(s: &str) -> String {
+  if s.is_empty() {
+    return String::new();
+  }
+  format!("'{}'", s.replace('\'', "'\\''"))
+}
+
+pub fn fish(optz: &Optz) -> String {
+  let mut optz = optz.clone();
+  optz.prepare();
+  let mut out = String::new();
+  fish_commands(&optz, &optz.name.clone(), None, &mut out);
+  out
+}
+
+fn fish_commands(
+  optz: &Optz,
+  program: &str,
+  condition: Option<String>,
+  out: &mut String,
+) {
+  let subcommands = visible_subcommands(optz).collect::<Vec<_>>();
+  for opt in &optz.options {
+    if matches!(opt.arg, Arg::Positional) || opt.hidden {
+      continue;
+    }
+    let mut line = format!("complete -c {}", program);
+    if let Some(condition) = &condition {
+      line.push_str(&format!(" -n {}", fish_quote(condition)));
+    }
+    for name in names(opt) {
+      if let Some(long) = name.strip_prefix("--") {
+        line.push_str(&format!(" -l {}", long));
+      } else if let Some(short) = name.strip_prefix('-') {
+        let flag = if short.chars().count() == 1 { "-s" } else { "-o" };
+        line.push_str(&format!(" {} {}", flag, short));
+      }
+    }
+    if let Arg::Arg = opt.arg {
+      line.push_str(" -r");
+      if !opt.possible_values.is_empty() {
+        line.push_str(&format!(
+          " -f -a {}",
+          fish_quote(&opt.possible_values.join(" "))
+        ));
+      }
+    }
+    let desc = description(optz, opt);
+    if !desc.is_empty() {
+      line.push_str(&format!(" -d {}", fish_quote(&desc)));
+    }
+    out.push_str(&line);
+    out.push('\n');
+  }
+  let names = subcommands
+    .iter()
+    .flat_map(|sub| std::iter::once(&sub.name).chain(&sub.aliases))
+    .cloned()
+    .collect::<Vec<_>>();
+  for sub in &subcommands {
+    let outer = match &condition {
+      Some(condition) => format!(
+        "{}; and not __fish_seen_subcommand_from {}",
+        condition,
+        names.join(" ")
+      ),
+      None => "__fish_use_subcommand".to_string(),
+    };
+    for name in std::iter::once(&sub.name).chain(&sub.aliases) {
+      let mut line = format!(
+        "complete -c {} -n {} -f -a {}",
+        program,
+        fish_quote(&outer),
+        fish_quote(name)
+      );
+      if let Some(desc) = &sub.description {
+        line.push_str(&format!(" -d {}", fish_quote(desc)));
+      }
+      out.push_str(&line);
+      out.push('\n');
+    }
+    let inner = format!(
+      "__fish_seen_subcommand_from {}",
+      std::iter::once(&sub.name)
+        .chain(&sub.aliases)
+        .cloned()
+        .collect::<Vec<_>>()
+        .join(" ")
+    );
+    fish_commands(sub, program, Some(inner), out);
+  }
+}
+
+fn fish_quote(s: &str) -> String {
+  format!("'{}'", s.replace('\\', "\\\\").replace('\'', "\\'"))
+}
@@ -0,0 +1,283 @@
+use crate::error::{OptzError, Result};
+use crate::messages::{self, Messages};
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const EXTENSIONS: &[&str] = &[
+  #[cfg(feature = "config-toml")]
+  "toml",
+  #[cfg(feature = "config-json")]
+  "json",
+  #[cfg(feature = "config-yaml")]
+  "yaml",
+  #[cfg(feature = "config-yaml")]
+  "yml",
+];
+
+type ParseResult<T> = std::result::Result<T, String>;
+
+type Parser = fn(&str, &Messages) -> ParseResult<Value>;
+
+#[cfg_attr(
+  not(any(
+    feature = "config-toml",
+    feature = "config-json",
+    feature = "config-yaml"
+  )),
+  allow(dead_code)
+)]
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum Value {
+  Scalar(String),
+  List(Vec<String>),
+  Table(Vec<(String, Value)>),
+}
+
+impl Value {
+  pub(crate) fn get(&self, name: &str) -> Option<&Value> {
+    let Value::Table(entries) = self else {
+      return None;
+    };
+    entries
+      .iter()
+      .find(|(key, _)| key == name || key.replace('_', "-") == name)
+      .map(|(_, value)| value)
+  }
+
+  pub(crate) fn keys(&self) -> Vec<&str> {
+    match self {
+      Value::Table(entries) => {
+        entries.iter().map(|(key, _)| key.as_str()).collect()
+      }
+      _ => Vec::new(),
+    }
+  }
+
+  pub(crate) fn values(&self) -> Option<Vec<String>> {
+    match self {
+      Value::Scalar(value) => Some(vec![value.clone()]),
+      Value::List(values) => Some(values.clone()),
+      Value::Table(_) => None,
+    }
+  }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct AppDirs {
+  pub app_name: Option<String>,
+  pub file_stem: String,
+}
+
+impl Default for AppDirs {
+  fn default() -> Self {
+    AppDirs {
+      app_name: None,
+      file_stem: "config".to_string(),
+    }
+  }
+}
+
+impl AppDirs {
+  pub fn app_name(mut self, name: &str) -> Self {
+    self.app_name = Some(name.to_string());
+    self
+  }
+
+  pub fn file_stem(mut self, stem: &str) -> Self {
+    self.file_stem = stem.to_string();
+    self
+  }
+
+  pub(crate) fn dirs(&self, app: &str) -> Vec<PathBuf> {
+    let app = self.app_name.as_deref().unwrap_or(app);
+    let var = |name: &str| {
+      env::var_os(name)
+        .filter(|v| !v.is_empty())
+        .map(PathBuf::from)
+    };
+    let home = var("HOME").or_else(|| var("USERPROFILE"));
+    let mut dirs = Vec::new();
+    match var("XDG_CONFIG_HOME").filter(|dir| dir.is_absolute()) {
+      Some(dir) => dirs.push(dir),
+      None => dirs.extend(home.as_ref().map(|h| h.join(".config"))),
+    }
+    if cfg!(target_os = "macos") {
+      dirs
+        .extend(home.map(|h| h.join("Library/Application Support")));
+    }
+    if cfg!(windows) {
+      dirs.extend(var("APPDATA"));
+    }
+    dirs.into_iter().map(|dir| dir.join(app)).collect()
+  }
+
+  pub(crate) fn find(&self, app: &str) -> Option<PathBuf> {
+    self
+      .dirs(app)
+      .iter()
+      .flat_map(|dir| {
+        EXTENSIONS.iter().map(move |ext| {
+          dir.join(format!("{}.{}", self.file_stem, ext))
+        })
+      })
+      .find(|path| path.is_file())
+  }
+}
+
+pub(crate) fn expand_home(path: &str) -> PathBuf {
+  let home =
+    env::var_os("HOME").or_else(|| env::var_os("USERPROFILE"));
+  match (path.strip_prefix("~/"), home) {
+    (Some(rest), Some(home)) => Path::new(&home).join(rest),
+    _ => PathBuf::from(path),
+  }
+}
+
+pub(crate) fn load(path: &Path, m: &Messages) -> Result<Value> {
+  let ext = path.extension().and_then(|e| e.to_str()).unwrap_or("");
+  let parse = parser(ext).ok_or_else(|| {
+    OptzError::InvalidValue(messages::fill(
+      &m.config_format,
+      &[&ext, &path.display()],
+    ))
+  })?;
+  let text = fs::read_to_string(path).map_err(|e| {
+    OptzError::InvalidValue(messages::fill(
+      &m.config_unreadable,
+      &[&path.display(), &e],
+    ))
+  })?;
+  parse(&text, m).map_err(|e| {
+    OptzError::InvalidValue(messages::fill(
+      &m.config_invalid,
+      &[&path.display(), &e],
+    ))
+  })
+}
+
+fn parser(ext: &str) -> Option<Parser> {
+  match ext {
+    #[cfg(feature = "config-toml")]
+    "toml" => Some(parse_toml),
+    #[cfg(feature = "config-json")]
+    "json" => Some(parse_json),
+    #[cfg(feature = "config-yaml")]
+    "yaml" | "yml" => Some(parse_yaml),
+    _ => None,
+  }
+}
+
+#[cfg(feature = "config-toml")]
+fn parse_toml(text: &str, m: &Messages) -> ParseResult<Value> {
+  fn scalar(value: toml::Value, m: &Messages) -> ParseResult<String> {
+    match value {
+      toml::Value::String(s) => Ok(s),
+      toml::Value::Integer(i) => Ok(i.to_string()),
+      toml::Value::Float(f) => Ok(f.to_string()),
+      toml::Value::Boolean(b) => Ok(b.to_string()),
+      toml::Value::Datetime(d) => Ok(d.to_string()),
+      _ => Err(m.config_nested.clone()),
+    }
+  }
+  fn convert(value: toml::Value, m: &Messages) -> ParseResult<Value> {
+    match value {
+      toml::Value::Array(items) => items
+        .into_iter()
+        .map(|item| scalar(item, m))
+        .collect::<ParseResult<_>>()
+        .map(Value::List),
+      toml::Value::Table(table) => table
+        .into_iter()
+        .map(|(key, value)| Ok((key, convert(value, m)?)))
+        .collect::<ParseResult<_>>()
+        .map(Value::Table),
+      value => scalar(value, m).map(Value::Scalar),
+    }
+  }
+  let table = text
+    .parse::<toml::Table>()
+    .map_err(|e| e.to_string().trim_end().to_string())?;
+  convert(toml::Value::Table(table), m)
+}
+
+#[cfg(feature = "config-json")]
+fn parse_json(text: &str, m: &Messages) -> ParseResult<Value> {
+  use serde_json::Value as Json;
+  fn scalar(value: Json, m: &Messages) -> ParseResult<Option<String>> {
+    match value {
+      Json::Null => Ok(None),
+      Json::String(s) => Ok(Some(s)),
+      Json::Bool(b) => Ok(Some(b.to_string())),
+      Json::Number(n) => Ok(Some(n.to_string())),
+      _ => Err(m.config_nested.clone()),
+    }
+  }
+  fn convert(value: Json, m: &Messages) -> ParseResult<Option<Value>> {
+    match value {
+      Json::Array(items) => items
+        .into_iter()
+        .filter_map(|item| scalar(item, m).transpose())
+        .collect::<ParseResult<_>>()
+        .map(|values| Some(Value::List(values))),
+      Json::Object(map) => map
+        .into_iter()
+        .filter_map(|(key, value)| {
+          convert(value, m).map(|v| v.map(|v| (key, v))).transpose()
+        })
+        .collect::<ParseResult<_>>()
+        .map(|entries| Some(Value::Table(entries))),
+      value => scalar(value, m).map(|s| s.map(Value::Scalar)),
+    }
+  }
+  match serde_json::from_str(text).map_err(|e| e.to_string())? {
+    value @ Json::Object(_) => {
+      Ok(convert(value, m)?.unwrap_or(Value::Table(Vec::new())))
+    }
+    _ => Err(m.config_top_level.clone()),
+  }
+}
+
+#[cfg(feature = "config-yaml")]
+fn parse_yaml(text: &str, m: &Messages) -> ParseResult<Value> {
+  use serde_yaml_ng::Value as Yaml;
+  fn scalar(value: Yaml, m: &Messages) -> ParseResult<Option<String>> {
+    match value {
+      Yaml::Null => Ok(None),
+      Yaml::String(s) => Ok(Some(s)),
+      Yaml::Bool(b) => Ok(Some(b.to_string())),
+      Yaml::Number(n) => Ok(Some(n.to_string())),
+      Yaml::Tagged(_) => Err(m.config_tagged.clone()),
+      _ => Err(m.config_nested.clone()),
+    }
+  }
+  fn convert(value: Yaml, m: &Messages) -> ParseResult<Option<Value>> {
+    match value {
+      Yaml::Sequence(items) => items
+        .into_iter()
+        .filter_map(|item| scalar(item, m).transpose())
+        .collect::<ParseResult<_>>()
+        .map(|values| Some(Value::List(values))),
+      Yaml::Mapping(map) => {
+        let mut entries = Vec::new();
+        for (key, value) in map {
+          let Some(key) = scalar(key, m)? else {
+            return Err(m.config_null_key.clone());
+          };
+          if let Some(value) = convert(value, m)? {
+            entries.push((key, value));
+          }
+        }
+        Ok(Some(Value::Table(entries)))
+      }
+      value => scalar(value, m).map(|s| s.map(Value::Scalar)),
+    }
+  }
+  match serde_yaml_ng::from_str(text).map_err(|e| e.to_string())? {
+    Yaml::Null => Ok(Value::Table(Vec::new())),
+    value @ Yaml::Mapping(_) => {
+      Ok(convert(value, m)?.unwrap_or(Value::Table(Vec::new())))
+    }
+    _ => Err(m.config_top_level.clone()),
+  }
+}
@@ -2,15 +2,75 @@ use std::fmt;
 
 #[derive(Debug)]
 pub enum OptzError {
-  MissingArgument,
+  Conflict(String),
+  DisplayHelp(String),
+  DisplayVersion(String),
+  InvalidValue(String),
+  LossyConversion(String),
+  MissingArgument(String),
+  MissingRequired(String),
+  NoSuchOption(String),
+  NumericOverflow(String),
   Parse(String),
+  UnknownOption(String),
+}
+
+impl OptzError {
+  pub(crate) fn map_message<F: Fn(&str) -> String>(
+    self,
+    f: F,
+  ) -> Self {
+    match self {
+      OptzError::Conflict(msg) => OptzError::Conflict(f(&msg)),
+      OptzError::InvalidValue(msg) => {
+        OptzError::InvalidValue(f(&msg))
+      }
+      OptzError::LossyConversion(msg) => {
+        OptzError::LossyConversion(f(&msg))
+      }
+      OptzError::MissingArgument(msg) => {
+        OptzError::MissingArgument(f(&msg))
+      }
+      OptzError::MissingRequired(msg) => {
+        OptzError::MissingRequired(f(&msg))
+      }
+      OptzError::NoSuchOption(msg) => {
+        OptzError::NoSuchOption(f(&msg))
+      }
+      OptzError::NumericOverflow(msg) => {
+        OptzError::NumericOverflow(f(&msg))
+      }
+      OptzError::Parse(msg) => OptzError::Parse(f(&msg)),
+      OptzError::UnknownOption(msg) => {
+        OptzError::UnknownOption(f(&msg))
+      }
+      e => e,
+    }
+  }
+
+  pub fn exit_code(&self) -> i32 {
+    match self {
+      OptzError::DisplayHelp(_) | OptzError::DisplayVersion(_) => 0,
+      OptzError::Parse(_) => 1,
+      _ => 2,
+    }
+  }
 }
 
 impl std::fmt::Display for OptzError {
   fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
     match self {
-      OptzError::MissingArgument => write!(f, "Missing argument"),
+      OptzError::Conflict(msg) => write!(f, "{}", msg),
+      OptzError::DisplayHelp(text) => write!(f, "{}", text),
+      OptzError::DisplayVersion(text) => write!(f, "{}", text),
+      OptzError::InvalidValue(msg) => write!(f, "{}", msg),
+      OptzError::LossyConversion(msg) => write!(f, "{}", msg),
+      OptzError::MissingArgument(msg) => write!(f, "{}", msg),
+      OptzError::MissingRequired(msg) => write!(f, "{}", msg),
+      OptzError::NoSuchOption(msg) => write!(f, "{}", msg),
+      OptzError::NumericOverflow(msg) => write!(f, "{}", msg),
       OptzError::Parse(msg) => write!(f, "{}", msg),
+      OptzError::UnknownOption(msg) => write!(f, "{}", msg),
     }
   }
 }
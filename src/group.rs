@@ -0,0 +1,42 @@
+#[derive(Clone, Debug, Default)]
+pub struct Group {
+  pub args: Vec<String>,
+  pub exclusive: bool,
+  pub name: String,
+  pub required: bool,
+}
+
+impl Group {
+  pub fn new(name: &str) -> Self {
+    Self {
+      name: name.to_owned(),
+      ..Default::default()
+    }
+  }
+
+  pub fn arg(mut self, arg: &str) -> Self {
+    self.args.push(arg.to_owned());
+    self
+  }
+
+  pub fn args<I, S>(mut self, args: I) -> Self
+  where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+  {
+    self
+      .args
+      .extend(args.into_iter().map(|arg| arg.as_ref().to_owned()));
+    self
+  }
+
+  pub fn exclusive(mut self, exclusive: bool) -> Self {
+    self.exclusive = exclusive;
+    self
+  }
+
+  pub fn required(mut self, required: bool) -> Self {
+    self.required = required;
+    self
+  }
+}
@@ -0,0 +1,445 @@
+use crate::color::{self, BOLD, GREEN};
+use crate::opt::{Arg, Opt, Optz};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Sort {
+  #[default]
+  Declaration,
+  Alphabetical,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Section {
+  pub title: String,
+  pub body: String,
+}
+
+pub fn parse_sections(text: &str) -> Vec<Section> {
+  let mut sections: Vec<Section> = Vec::new();
+  for line in text.lines() {
+    if let Some(title) = line.strip_prefix('#') {
+      sections.push(Section {
+        title: title.trim_start_matches('#').trim().to_string(),
+        body: String::new(),
+      });
+      continue;
+    }
+    if sections.is_empty() {
+      if line.trim().is_empty() {
+        continue;
+      }
+      sections.push(Section {
+        title: "About".to_string(),
+        body: String::new(),
+      });
+    }
+    let section = sections.last_mut().unwrap();
+    section.body.push_str(line);
+    section.body.push('\n');
+  }
+  for section in sections.iter_mut() {
+    section.body = section.body.trim().to_string();
+  }
+  sections
+}
+
+pub(crate) fn render(optz: &Optz, color: bool, long: bool) -> String {
+  let renderer = Renderer { optz, color, long };
+  if let Some(template) = &optz.help_template {
+    return renderer.template(template);
+  }
+  let mut out = String::new();
+  out.push_str(&renderer.before_help());
+  out.push_str(&renderer.version());
+  out.push_str(&renderer.usage());
+  out.push_str(&renderer.description());
+  let arguments = renderer.arguments();
+  if !arguments.is_empty() {
+    if !out.ends_with("\n\n") {
+      out.push('\n');
+    }
+    out.push_str(&arguments);
+    out.push_str(&format!(
+      "\n{}\n",
+      renderer.paint(BOLD, &renderer.t(&optz.messages.options))
+    ));
+  }
+  out.push_str(&renderer.options());
+  out.push_str(&renderer.commands());
+  out.push_str(&renderer.sections());
+  out.push_str(&renderer.examples());
+  out.push_str(&renderer.after_help());
+  out.push_str(&renderer.authors());
+  out
+}
+
+struct Renderer<'a> {
+  optz: &'a Optz,
+  color: bool,
+  long: bool,
+}
+
+impl Renderer<'_> {
+  fn t(&self, s: &str) -> String {
+    match self.optz.help_translator {
+      Some(translate) => translate(s),
+      None => s.to_string(),
+    }
+  }
+
+  fn paint(&self, style: &str, text: &str) -> String {
+    color::paint(self.color, style, text)
+  }
+
+  fn before_help(&self) -> String {
+    match &self.optz.before_help {
+      Some(text) => format!("{}\n\n", self.t(text)),
+      None => String::new(),
+    }
+  }
+
+  fn after_help(&self) -> String {
+    match &self.optz.after_help {
+      Some(text) => format!("\n{}\n", self.t(text)),
+      None => String::new(),
+    }
+  }
+
+  fn examples(&self) -> String {
+    if self.optz.examples.is_empty() {
+      return String::new();
+    }
+    let mut out = format!(
+      "\n{}\n",
+      self.paint(BOLD, &self.t(&self.optz.messages.examples))
+    );
+    for (i, (command, description)) in
+      self.optz.examples.iter().enumerate()
+    {
+      if i > 0 {
+        out.push('\n');
+      }
+      out.push_str(&format!("  {}\n", self.t(description)));
+      out.push_str(&format!("    $ {}\n", command));
+    }
+    out
+  }
+
+  fn version(&self) -> String {
+    match &self.optz.version {
+      Some(version) => {
+        format!("{} {}\n", self.optz.full_name(), version)
+      }
+      None => String::new(),
+    }
+  }
+
+  fn usage(&self) -> String {
+    match &self.optz.usage {
+      Some(usage) => format!("{}\n", self.t(usage)),
+      None => String::new(),
+    }
+  }
+
+  fn description(&self) -> String {
+    match &self.optz.description {
+      Some(description) if !self.optz.hide_description => {
+        format!("\n{}\n\n", self.t(description))
+      }
+      _ => String::new(),
+    }
+  }
+
+  fn template(&self, template: &str) -> String {
+    let mut out = String::new();
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+      out.push_str(&rest[..start]);
+      rest = &rest[start + 1..];
+      let section = rest
+        .find('}')
+        .and_then(|end| Some((end, self.section(&rest[..end])?)));
+      match section {
+        Some((end, section)) => {
+          out.push_str(section.trim_matches('\n'));
+          rest = &rest[end + 1..];
+        }
+        None => out.push('{'),
+      }
+    }
+    out.push_str(rest);
+    if !out.ends_with('\n') {
+      out.push('\n');
+    }
+    out
+  }
+
+  fn section(&self, name: &str) -> Option<String> {
+    let section = match name {
+      "version" => self.version(),
+      "usage" => self.usage(),
+      "description" => self.description(),
+      "arguments" => self.arguments(),
+      "options" => self.options(),
+      "commands" => self.commands(),
+      "sections" => self.sections(),
+      "examples" => self.examples(),
+      "before-help" => self.before_help(),
+      "after-help" => self.after_help(),
+      "authors" => self.authors(),
+      _ => return None,
+    };
+    Some(section)
+  }
+
+  fn arguments(&self) -> String {
+    let positionals = self.optz.positionals();
+    if positionals.is_empty() {
+      return String::new();
+    }
+    let mut out = String::new();
+    out.push_str(&format!(
+      "{}\n",
+      self.paint(BOLD, &self.t(&self.optz.messages.arguments))
+    ));
+    for opt in positionals.iter().filter(|opt| !opt.hidden) {
+      let mut res = format!(
+        "  {}{} ",
+        self.paint(GREEN, &opt.long),
+        " ".repeat(16usize.saturating_sub(opt.long.len()))
+      );
+      if let Some(desc) = &opt.description {
+        res.push_str(&self.t(desc));
+      }
+      out.push_str(res.trim_end());
+      out.push('\n');
+    }
+    out
+  }
+
+  fn options(&self) -> String {
+    let mut options = self
+      .optz
+      .options
+      .iter()
+      .filter(|opt| {
+        !matches!(opt.arg, Arg::Positional) && !opt.hidden
+      })
+      .collect::<Vec<_>>();
+    options.sort_by(|a, b| {
+      let order = |opt: &Opt| opt.display_order.unwrap_or(usize::MAX);
+      order(a).cmp(&order(b)).then_with(|| {
+        match self.optz.sort_help {
+          Sort::Declaration => std::cmp::Ordering::Equal,
+          Sort::Alphabetical => a.long.cmp(&b.long),
+        }
+      })
+    });
+    let mut headings: Vec<Option<&str>> = vec![None];
+    for opt in &options {
+      let heading = opt.help_heading.as_deref();
+      if !headings.contains(&heading) {
+        headings.push(heading);
+      }
+    }
+    let width = options
+      .iter()
+      .map(|opt| option_label(opt).len() + 1)
+      .fold(12, usize::max);
+    let mut out = String::new();
+    for heading in headings {
+      if let Some(heading) = heading {
+        let title = format!("{}:", self.t(heading));
+        out.push_str(&format!("\n{}\n", self.paint(BOLD, &title)));
+      }
+      for opt in options
+        .iter()
+        .filter(|o| o.help_heading.as_deref() == heading)
+      {
+        out.push_str(self.option(opt, width).trim_end());
+        out.push('\n');
+      }
+    }
+    out
+  }
+
+  fn commands(&self) -> String {
+    let subcommands = self
+      .optz
+      .subcommands
+      .iter()
+      .filter(|sub| !sub.hidden)
+      .collect::<Vec<_>>();
+    if subcommands.is_empty() && !self.optz.help_subcommand() {
+      return String::new();
+    }
+    let mut out = format!(
+      "\n{}\n",
+      self.paint(BOLD, &self.t(&self.optz.messages.commands))
+    );
+    for sub in subcommands {
+      let mut res = format!(
+        "  {}{} ",
+        self.paint(GREEN, &sub.name),
+        " ".repeat(16usize.saturating_sub(sub.name.len()))
+      );
+      if let Some(desc) = &sub.description {
+        res.push_str(&self.t(desc));
+      }
+      if sub.experimental {
+        res.push_str(&format!(
+          " [{}]",
+          self.t(&self.optz.messages.experimental)
+        ));
+      }
+      if !sub.aliases.is_empty() {
+        res.push_str(&format!(
+          " [{}: {}]",
+          self.t(&self.optz.messages.aliases),
+          sub.aliases.join(", ")
+        ));
+      }
+      if !sub.renamed_from.is_empty() {
+        res.push_str(&format!(
+          " [{} {}]",
+          self.t(&self.optz.messages.renamed_from),
+          sub.renamed_from.join(", ")
+        ));
+      }
+      out.push_str(res.trim_end());
+      out.push('\n');
+    }
+    if self.optz.help_subcommand() {
+      out.push_str(&format!(
+        "  {}{}{}\n",
+        self.paint(GREEN, "help"),
+        " ".repeat(13),
+        self.t(&self.optz.messages.help_command)
+      ));
+    }
+    out
+  }
+
+  fn option(&self, opt: &Opt, width: usize) -> String {
+    let mut res = "  ".to_owned();
+    if let Some(short) = &opt.short {
+      res.push_str(&self.paint(GREEN, short));
+      res.push_str(", ");
+    } else {
+      res.push_str("    ");
+    }
+    let long = option_label(opt);
+    res.push_str(&self.paint(GREEN, &long));
+    res.push_str(&" ".repeat(width - long.len() + 1));
+    let desc = match &opt.long_description {
+      Some(desc) if self.long => Some(desc),
+      _ => opt.description.as_ref(),
+    };
+    if let Some(desc) = desc {
+      let indent = format!("\n{}", " ".repeat(7 + width));
+      res.push_str(
+        &self.t(desc).lines().collect::<Vec<_>>().join(&indent),
+      );
+    }
+    for annotation in self.annotations(opt) {
+      res.push_str(&format!(" [{}]", annotation));
+    }
+    res
+  }
+
+  fn annotations(&self, opt: &Opt) -> Vec<String> {
+    let mut annotations = Vec::new();
+    if opt.deprecated.is_some() {
+      annotations.push(self.t(&self.optz.messages.deprecated));
+    }
+    if !opt.renamed_from.is_empty() {
+      annotations.push(format!(
+        "{} {}",
+        self.t(&self.optz.messages.renamed_from),
+        opt.renamed_from.join(", ")
+      ));
+    }
+    let aliases = opt
+      .aliases
+      .iter()
+      .filter(|(_, visible)| *visible)
+      .map(|(alias, _)| alias.as_str())
+      .collect::<Vec<_>>();
+    if !aliases.is_empty() {
+      annotations.push(format!(
+        "{}: {}",
+        self.t(&self.optz.messages.aliases),
+        aliases.join(", ")
+      ));
+    }
+    if self.optz.hide_annotations {
+      return annotations;
+    }
+    if let Some(env) = &opt.env {
+      annotations.push(format!(
+        "{}: {}",
+        self.t(&self.optz.messages.env),
+        env
+      ));
+    }
+    let default = opt
+      .default_value
+      .clone()
+      .or_else(|| opt.default_value_fn.map(|f| f()))
+      .filter(|_| !opt.secret);
+    if let Some(default) = default {
+      annotations.push(format!(
+        "{}: {}",
+        self.t(&self.optz.messages.default),
+        default
+      ));
+    }
+    if !opt.possible_values.is_empty() {
+      annotations.push(format!(
+        "{}: {}",
+        self.t(&self.optz.messages.possible),
+        opt.possible_values.join("|")
+      ));
+    }
+    annotations
+  }
+
+  fn sections(&self) -> String {
+    let mut out = String::new();
+    for section in self.optz.long_about.iter() {
+      out.push('\n');
+      let title =
+        format!("{}:", self.t(&section.title).to_uppercase());
+      out.push_str(&self.paint(BOLD, &title));
+      out.push('\n');
+      for line in self.t(&section.body).lines() {
+        if line.is_empty() {
+          out.push('\n');
+        } else {
+          out.push_str("  ");
+          out.push_str(line);
+          out.push('\n');
+        }
+      }
+    }
+    out
+  }
+
+  fn authors(&self) -> String {
+    if self.optz.authors.is_empty() || self.optz.hide_authors {
+      return String::new();
+    }
+    format!(
+      "\n{} {}\n",
+      self.paint(BOLD, &self.t(&self.optz.messages.authors)),
+      self.optz.authors.join(", ")
+    )
+  }
+}
+
+fn option_label(opt: &Opt) -> String {
+  match opt.arg {
+    Arg::Arg => format!("{} {}", opt.long, opt.placeholder()),
+    _ => opt.long.clone(),
+  }
+}
@@ -1,5 +1,28 @@
+mod color;
+mod complete;
+mod config;
 mod error;
+mod group;
+mod help;
+mod lint;
+mod macros;
+mod messages;
 mod opt;
+pub mod parsers;
+mod run;
+mod suggest;
+pub mod testing;
+pub mod validators;
 
+pub use crate::color::ColorChoice;
+pub use crate::config::AppDirs;
 pub use crate::error::OptzError;
-pub use crate::opt::{Opt, Optz};
+pub use crate::group::Group;
+pub use crate::help::{Section, Sort};
+pub use crate::lint::LintWarning;
+pub use crate::messages::Messages;
+pub use crate::opt::{
+  Matched, Matches, Metadata, Opt, OptMatch, Optz, RestArg, Route,
+  Tokenizer, ValueParser, ValueSource,
+};
+pub use crate::run::run;
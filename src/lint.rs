@@ -0,0 +1,154 @@
+use crate::opt::{Arg, Optz};
+use std::fmt;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct LintWarning {
+  pub option: Option<String>,
+  pub message: String,
+}
+
+impl fmt::Display for LintWarning {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match &self.option {
+      Some(option) => write!(f, "{}: {}", option, self.message),
+      None => write!(f, "{}", self.message),
+    }
+  }
+}
+
+pub fn lint(optz: &Optz) -> Vec<LintWarning> {
+  let mut warnings = Vec::new();
+  let mut warn = |option: Option<&str>, message: String| {
+    warnings.push(LintWarning {
+      option: option.map(|o| o.to_owned()),
+      message,
+    })
+  };
+
+  let value_names = optz
+    .options
+    .iter()
+    .filter(|opt| matches!(opt.arg, Arg::Arg))
+    .map(|opt| (opt, opt.value_name.as_deref().unwrap_or(&opt.name)))
+    .collect::<Vec<_>>();
+  let uppercase = |value_name: &str| {
+    value_name.chars().any(|c| c.is_ascii_uppercase())
+      && !value_name.chars().any(|c| c.is_ascii_lowercase())
+  };
+  let mut referenced = optz
+    .groups
+    .iter()
+    .flat_map(|group| group.args.iter())
+    .collect::<Vec<_>>();
+  for opt in &optz.options {
+    referenced.extend(&opt.requires);
+    referenced.extend(&opt.conflicts_with);
+    referenced
+      .extend(opt.required_if_eq.iter().map(|(name, _)| name));
+  }
+
+  for (i, opt) in optz.options.iter().enumerate() {
+    let name = Some(opt.long.as_str());
+    if opt.description.is_none() {
+      warn(name, "missing description".to_string());
+    }
+    if !opt.name.chars().all(|c| {
+      c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-'
+    }) {
+      warn(
+        name,
+        format!(
+          "name '{}' is not lowercase kebab-case (e.g. '{}')",
+          opt.name,
+          opt.name.to_ascii_lowercase().replace('_', "-")
+        ),
+      );
+    }
+    if let Some(short) = &opt.short
+      && (!short.starts_with('-') || short.chars().count() != 2)
+    {
+      warn(
+        name,
+        format!(
+          "short '{}' should be a dash and one character (e.g. '-{}')",
+          short,
+          short.trim_start_matches('-').chars().next().unwrap_or('x')
+        ),
+      );
+    }
+    if matches!(opt.arg, Arg::Flag)
+      && opt.default_value.as_deref() == Some("true")
+    {
+      warn(
+        name,
+        "flag defaults to \"true\" and can never be turned off; \
+         remove the default or invert the flag (e.g. --no-...)"
+          .to_string(),
+      );
+    }
+    for required in &opt.requires {
+      if !optz.options.iter().any(|o| &o.name == required) {
+        warn(name, format!("requires unknown option '{}'", required));
+      }
+    }
+    for conflict in &opt.conflicts_with {
+      if !optz.options.iter().any(|o| &o.name == conflict) {
+        warn(
+          name,
+          format!("conflicts with unknown option '{}'", conflict),
+        );
+      }
+    }
+    if let Some((first, first_name)) = value_names.first()
+      && let Some((_, value_name)) =
+        value_names.iter().find(|(o, _)| std::ptr::eq(*o, opt))
+      && uppercase(value_name) != uppercase(first_name)
+    {
+      warn(
+        name,
+        format!(
+          "value name '{}' does not match the style of '{}' \
+           used by {}",
+          value_name, first_name, first.long
+        ),
+      );
+    }
+    if !referenced.is_empty()
+      && !referenced.contains(&&opt.name)
+      && opt.requires.is_empty()
+      && opt.conflicts_with.is_empty()
+      && opt.required_if_eq.is_empty()
+    {
+      warn(
+        name,
+        "not referenced by any group or requirement".to_string(),
+      );
+    }
+    if optz.options[..i].iter().any(|o| o.name == opt.name) {
+      warn(name, "defined more than once".to_string());
+    }
+    if let Some(short) = &opt.short
+      && optz.options[..i]
+        .iter()
+        .any(|o| o.short.as_ref() == Some(short))
+    {
+      warn(name, format!("short '{}' is already used", short));
+    }
+  }
+
+  for group in &optz.groups {
+    for arg in &group.args {
+      if !optz.options.iter().any(|o| &o.name == arg) {
+        warn(
+          None,
+          format!(
+            "group '{}' references unknown option '{}'",
+            group.name, arg
+          ),
+        );
+      }
+    }
+  }
+
+  warnings
+}
@@ -0,0 +1,17 @@
+#[macro_export]
+macro_rules! crate_info {
+  () => {{
+    let description = env!("CARGO_PKG_DESCRIPTION");
+    let mut optz = $crate::Optz::new(env!("CARGO_PKG_NAME"))
+      .version(env!("CARGO_PKG_VERSION"));
+    optz.authors = env!("CARGO_PKG_AUTHORS")
+      .split(':')
+      .filter(|author| !author.is_empty())
+      .map(|author| author.to_string())
+      .collect();
+    if !description.is_empty() {
+      optz = optz.description(description);
+    }
+    optz
+  }};
+}
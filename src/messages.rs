@@ -0,0 +1,320 @@
+#[cfg(feature = "fluent")]
+use crate::error::{OptzError, Result};
+use std::fmt::Display;
+
+// The most values any built-in message is filled with.
+#[cfg(feature = "fluent")]
+const MAX_ARGS: usize = 4;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Messages {
+  pub usage: String,
+  pub arguments: String,
+  pub options: String,
+  pub commands: String,
+  pub examples: String,
+  pub authors: String,
+  pub show_help: String,
+  pub show_version: String,
+  pub help_command: String,
+  pub enable_experimental: String,
+  pub config_file: String,
+  pub deprecated: String,
+  pub renamed_from: String,
+  pub aliases: String,
+  pub experimental: String,
+  pub env: String,
+  pub default: String,
+  pub possible: String,
+  pub missing_argument: String,
+  pub required: String,
+  pub required_if: String,
+  pub requires: String,
+  pub conflict: String,
+  pub exclusive: String,
+  pub group_required: String,
+  pub group_conflict: String,
+  pub too_few_values: String,
+  pub too_many_values: String,
+  pub forwarded_missing: String,
+  pub unknown_option: String,
+  pub unknown_option_path: String,
+  pub command_required: String,
+  pub unknown_command: String,
+  pub did_you_mean: String,
+  pub or: String,
+  pub ambiguous_command: String,
+  pub no_such_option: String,
+  pub no_such_command: String,
+  pub require_equals: String,
+  pub experimental_command: String,
+  pub swallowed_command: String,
+  pub invalid_value: String,
+  pub invalid_value_at: String,
+  pub invalid_positional: String,
+  pub expected_one_of: String,
+  pub ambiguous_value: String,
+  pub unsafe_value: String,
+  pub not_a_number: String,
+  pub not_in_range: String,
+  pub out_of_range: String,
+  pub not_whole: String,
+  pub from_env: String,
+  pub from_config_file: String,
+  pub deprecated_warning: String,
+  pub renamed_warning: String,
+  pub command_renamed_warning: String,
+  pub config_format: String,
+  pub config_unreadable: String,
+  pub config_invalid: String,
+  pub config_nested: String,
+  pub config_top_level: String,
+  pub config_tagged: String,
+  pub config_null_key: String,
+}
+
+impl Default for Messages {
+  fn default() -> Self {
+    Messages {
+      usage: "Usage:".to_string(),
+      arguments: "Arguments:".to_string(),
+      options: "Options:".to_string(),
+      commands: "Commands:".to_string(),
+      examples: "EXAMPLES:".to_string(),
+      authors: "Authors:".to_string(),
+      show_help: "Show help".to_string(),
+      show_version: "Show version".to_string(),
+      help_command: "Show help for a command".to_string(),
+      enable_experimental: "Allow experimental commands".to_string(),
+      config_file: "Load options from a config file".to_string(),
+      deprecated: "deprecated".to_string(),
+      renamed_from: "renamed from".to_string(),
+      aliases: "aliases".to_string(),
+      experimental: "experimental".to_string(),
+      env: "env".to_string(),
+      default: "default".to_string(),
+      possible: "possible".to_string(),
+      missing_argument: "Missing argument".to_string(),
+      required: "{} is required".to_string(),
+      required_if: "{} is required when {} is {}".to_string(),
+      requires: "{} requires {}".to_string(),
+      conflict: "{} cannot be used with {}".to_string(),
+      exclusive: "{} cannot be used with other arguments".to_string(),
+      group_required: "one of {} is required (group '{}')"
+        .to_string(),
+      group_conflict: "{} cannot be used with {} (group '{}')"
+        .to_string(),
+      too_few_values: "{} expects at least {} value(s), got {}"
+        .to_string(),
+      too_many_values: "{} expects at most {} value(s), got {}"
+        .to_string(),
+      forwarded_missing: "expected arguments for {} after '--'"
+        .to_string(),
+      unknown_option: "unknown option '{}'; to pass it as a \
+                       positional argument, put it after '--' \
+                       (e.g. '-- {}')"
+        .to_string(),
+      unknown_option_path: " or write it as './{}'".to_string(),
+      command_required: "a command is required, expected one of {}"
+        .to_string(),
+      unknown_command: "unknown command '{}'".to_string(),
+      did_you_mean: ", did you mean {}?".to_string(),
+      or: "or".to_string(),
+      ambiguous_command: "ambiguous command '{}', could be {}"
+        .to_string(),
+      no_such_option: "no option named '{}'".to_string(),
+      no_such_command: "no command named '{}'".to_string(),
+      require_equals: "{} requires its value to be attached with \
+                       '=', e.g. {}={}"
+        .to_string(),
+      experimental_command: "'{}' is experimental; pass \
+                             --enable-experimental or set {}=1 to \
+                             use it"
+        .to_string(),
+      swallowed_command: " ({} took '{}' as its value)".to_string(),
+      invalid_value: "invalid value {} for {}: {}".to_string(),
+      invalid_value_at: "invalid value {} (#{}) for {}: {}"
+        .to_string(),
+      invalid_positional: "invalid positional argument {}: {}"
+        .to_string(),
+      expected_one_of: "expected one of {}".to_string(),
+      ambiguous_value: "ambiguous, could be {}".to_string(),
+      unsafe_value: "value for {} contains a NUL byte or line break; \
+                     refusing to pass it to a subprocess or \
+                     environment"
+        .to_string(),
+      not_a_number: "value {} for {} is not a number".to_string(),
+      not_in_range: "value {} for {} is not in {}".to_string(),
+      out_of_range: "value {} for {} is out of range for {}"
+        .to_string(),
+      not_whole: "value {} for {} is not a whole number and cannot \
+                  be converted to {} without loss"
+        .to_string(),
+      from_env: "{} (from {})".to_string(),
+      from_config_file: "{} (from config file)".to_string(),
+      deprecated_warning: "warning: {} is deprecated: {}".to_string(),
+      renamed_warning: "warning: {} has been renamed to {}"
+        .to_string(),
+      command_renamed_warning: "warning: command {} has been \
+                                renamed to {}"
+        .to_string(),
+      config_format: "unsupported config format '{}' for {}"
+        .to_string(),
+      config_unreadable: "cannot read {}: {}".to_string(),
+      config_invalid: "{}: {}".to_string(),
+      config_nested: "nested lists and tables are not supported"
+        .to_string(),
+      config_top_level: "expected a table at the top level".to_string(),
+      config_tagged: "tagged values are not supported".to_string(),
+      config_null_key: "null keys are not supported".to_string(),
+    }
+  }
+}
+
+#[cfg(feature = "fluent")]
+impl Messages {
+  /// Builds messages from Fluent (FTL) source, keeping the English
+  /// default for any message it leaves out. Message ids are the field
+  /// names in kebab case, e.g. `missing-argument`, and `{ $arg0 }`,
+  /// `{ $arg1 }`, ... stand for the values the message is filled
+  /// with. Those values are only known at parse time, so selectors on
+  /// them are not supported.
+  pub fn from_fluent(source: &str) -> Result<Self> {
+    use fluent_bundle::{FluentArgs, FluentBundle, FluentResource};
+
+    let resource = FluentResource::try_new(source.to_string())
+      .map_err(|(_, errors)| {
+        let e = &errors[0];
+        OptzError::Parse(format!(
+          "invalid Fluent source at byte {}: {}",
+          e.pos.start, e
+        ))
+      })?;
+    let mut bundle = FluentBundle::default();
+    bundle.set_use_isolating(false);
+    bundle.add_resource(resource).map_err(|errors| {
+      OptzError::Parse(format!("invalid Fluent source: {}", errors[0]))
+    })?;
+    let mut args = FluentArgs::new();
+    for i in 0..MAX_ARGS {
+      args.set(format!("arg{}", i), format!("{{{}}}", i));
+    }
+    let mut messages = Messages::default();
+    for (name, text) in messages.fields_mut() {
+      let id = name.replace('_', "-");
+      let message = bundle.get_message(&id);
+      let Some(pattern) = message.and_then(|m| m.value()) else {
+        continue;
+      };
+      let mut errors = Vec::new();
+      *text = bundle
+        .format_pattern(pattern, Some(&args), &mut errors)
+        .into_owned();
+      if let Some(e) = errors.first() {
+        return Err(OptzError::Parse(format!("{}: {}", id, e)));
+      }
+    }
+    Ok(messages)
+  }
+
+  fn fields_mut(&mut self) -> [(&'static str, &mut String); 63] {
+    [
+      ("usage", &mut self.usage),
+      ("arguments", &mut self.arguments),
+      ("options", &mut self.options),
+      ("commands", &mut self.commands),
+      ("examples", &mut self.examples),
+      ("authors", &mut self.authors),
+      ("show_help", &mut self.show_help),
+      ("show_version", &mut self.show_version),
+      ("help_command", &mut self.help_command),
+      ("enable_experimental", &mut self.enable_experimental),
+      ("config_file", &mut self.config_file),
+      ("deprecated", &mut self.deprecated),
+      ("renamed_from", &mut self.renamed_from),
+      ("aliases", &mut self.aliases),
+      ("experimental", &mut self.experimental),
+      ("env", &mut self.env),
+      ("default", &mut self.default),
+      ("possible", &mut self.possible),
+      ("missing_argument", &mut self.missing_argument),
+      ("required", &mut self.required),
+      ("required_if", &mut self.required_if),
+      ("requires", &mut self.requires),
+      ("conflict", &mut self.conflict),
+      ("exclusive", &mut self.exclusive),
+      ("group_required", &mut self.group_required),
+      ("group_conflict", &mut self.group_conflict),
+      ("too_few_values", &mut self.too_few_values),
+      ("too_many_values", &mut self.too_many_values),
+      ("forwarded_missing", &mut self.forwarded_missing),
+      ("unknown_option", &mut self.unknown_option),
+      ("unknown_option_path", &mut self.unknown_option_path),
+      ("command_required", &mut self.command_required),
+      ("unknown_command", &mut self.unknown_command),
+      ("did_you_mean", &mut self.did_you_mean),
+      ("or", &mut self.or),
+      ("ambiguous_command", &mut self.ambiguous_command),
+      ("no_such_option", &mut self.no_such_option),
+      ("no_such_command", &mut self.no_such_command),
+      ("require_equals", &mut self.require_equals),
+      ("experimental_command", &mut self.experimental_command),
+      ("swallowed_command", &mut self.swallowed_command),
+      ("invalid_value", &mut self.invalid_value),
+      ("invalid_value_at", &mut self.invalid_value_at),
+      ("invalid_positional", &mut self.invalid_positional),
+      ("expected_one_of", &mut self.expected_one_of),
+      ("ambiguous_value", &mut self.ambiguous_value),
+      ("unsafe_value", &mut self.unsafe_value),
+      ("not_a_number", &mut self.not_a_number),
+      ("not_in_range", &mut self.not_in_range),
+      ("out_of_range", &mut self.out_of_range),
+      ("not_whole", &mut self.not_whole),
+      ("from_env", &mut self.from_env),
+      ("from_config_file", &mut self.from_config_file),
+      ("deprecated_warning", &mut self.deprecated_warning),
+      ("renamed_warning", &mut self.renamed_warning),
+      ("command_renamed_warning", &mut self.command_renamed_warning),
+      ("config_format", &mut self.config_format),
+      ("config_unreadable", &mut self.config_unreadable),
+      ("config_invalid", &mut self.config_invalid),
+      ("config_nested", &mut self.config_nested),
+      ("config_top_level", &mut self.config_top_level),
+      ("config_tagged", &mut self.config_tagged),
+      ("config_null_key", &mut self.config_null_key),
+    ]
+  }
+}
+
+// Fills each `{}` in a message with the next argument and each `{N}`
+// with argument N, so translations can reorder the text around them.
+pub(crate) fn fill(template: &str, args: &[&dyn Display]) -> String {
+  let mut out = String::new();
+  let mut next = 0;
+  let mut rest = template;
+  while let Some(start) = rest.find('{') {
+    out.push_str(&rest[..start]);
+    rest = &rest[start + 1..];
+    let placeholder = rest.find('}').and_then(|end| {
+      let index = match &rest[..end] {
+        "" => next,
+        digits => digits.parse().ok()?,
+      };
+      Some((index, end))
+    });
+    match placeholder {
+      Some((index, end)) => {
+        if end == 0 {
+          next += 1;
+        }
+        if let Some(arg) = args.get(index) {
+          out.push_str(&arg.to_string());
+        }
+        rest = &rest[end + 1..];
+      }
+      None => out.push('{'),
+    }
+  }
+  out.push_str(rest);
+  out
+}
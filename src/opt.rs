@@ -1,42 +1,186 @@
+use crate::color::ColorChoice;
+use crate::complete;
+use crate::config::{self, AppDirs, Value};
 use crate::error::{OptzError, Result};
-use std;
+use crate::group::Group;
+use crate::help::{self, Section, Sort};
+use crate::lint::{self, LintWarning};
+use crate::messages::{self, Messages};
+use crate::parsers;
+use crate::suggest;
+use crate::validators::Validator;
 use std::any::Any;
 use std::env;
+use std::ffi::{OsStr, OsString};
 use std::fmt;
+use std::io::{self, IsTerminal, Write};
+use std::net::SocketAddr;
+use std::num::{IntErrorKind, ParseIntError};
+use std::ops::{Bound, RangeBounds};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
 use std::str::FromStr;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
 
-#[derive(Debug, Default)]
+pub type ValueParser = Arc<
+  dyn Fn(
+      &str,
+    ) -> std::result::Result<Arc<dyn Any + Send + Sync>, String>
+    + Send
+    + Sync,
+>;
+
+const REDACTED: &str = "***";
+
+struct EnvVar {
+  name: String,
+  default: String,
+  description: String,
+}
+
+pub type Tokenizer = fn(&[String]) -> Option<(usize, Vec<String>)>;
+
+type DefaultFrom = Arc<
+  dyn Fn(&(dyn Any + Send + Sync)) -> Option<String> + Send + Sync,
+>;
+
+#[derive(Clone, Debug, Default)]
+pub struct Metadata {
+  pub name: String,
+  pub version: Option<String>,
+  pub description: Option<String>,
+}
+
+#[derive(Clone, Default)]
 pub struct Optz {
   pub args: Vec<String>,
   pub handler: Option<fn(&Optz) -> Result<()>>,
   pub name: String,
+  pub version: Option<String>,
   pub usage: Option<String>,
   pub description: Option<String>,
+  pub long_about: Vec<Section>,
   pub authors: Vec<String>,
   pub options: Vec<Opt>,
-  pub config: Option<Box<dyn Any>>,
-  pub rest: Vec<String>,
+  pub groups: Vec<Group>,
+  pub config: Option<Arc<dyn Any + Send + Sync>>,
+  pub lenient: bool,
+  pub rest: Vec<RestArg>,
+  pub strict: bool,
+  pub slash_options: bool,
+  pub value_separators: Vec<char>,
+  pub expand_env: bool,
+  pub forward_to: Option<String>,
+  pub forwarded: Vec<String>,
+  pub matched: Vec<Matched>,
+  pub tokenizers: Vec<Tokenizer>,
+  pub raw_args: Vec<String>,
+  pub os_args: Vec<OsString>,
+  pub help_translator: Option<fn(&str) -> String>,
+  pub hide_description: bool,
+  pub hide_authors: bool,
+  pub hide_annotations: bool,
+  pub color: ColorChoice,
+  pub help_template: Option<String>,
+  pub before_help: Option<String>,
+  pub after_help: Option<String>,
+  pub examples: Vec<(String, String)>,
+  pub sort_help: Sort,
+  pub paged_help: bool,
+  pub disable_help_flag: bool,
+  pub help_flag_names: Vec<String>,
+  pub arg_required_else_help: bool,
+  pub return_help: bool,
+  pub messages: Messages,
+  pub command_path: Vec<String>,
+  pub subcommands: Vec<Optz>,
+  pub subcommand_matches: Option<Box<Optz>>,
+  pub(crate) arg_offset: usize,
+  pub aliases: Vec<String>,
+  pub renamed_from: Vec<String>,
+  pub infer_subcommands: bool,
+  pub subcommand_required: bool,
+  pub default_subcommand: Option<String>,
+  pub allow_external_subcommands: bool,
+  pub external_subcommand: Option<(String, Vec<String>)>,
+  pub multicall: bool,
+  pub propagate_version: bool,
+  pub hidden: bool,
+  pub experimental: bool,
+  pub env_prefix: Option<String>,
+  pub config_file: Option<String>,
+  pub config_flag: bool,
+  pub(crate) config_table: Option<Value>,
+  pub config_search: Option<AppDirs>,
 }
 
 impl Optz {
   pub fn new(name: &str) -> Self {
-    Optz::from_args(name, env::args().collect())
+    Optz::from_args_os(name, env::args_os().collect())
   }
 
   pub fn from_args<T: AsRef<str>>(name: &str, args: Vec<T>) -> Self {
+    let raw_args = args
+      .into_iter()
+      .map(|arg| arg.as_ref().to_string())
+      .collect::<Vec<_>>();
     Self {
-      args: args
-        .into_iter()
-        .skip(1)
-        .map(|arg| arg.as_ref().to_string())
-        .collect::<Vec<_>>(), // Collect into Vec
+      args: raw_args.iter().skip(1).cloned().collect(),
+      raw_args,
       name: name.into(),
       ..Default::default()
     }
   }
 
-  pub fn config<T: 'static>(mut self, config: T) -> Self {
-    self.config = Some(Box::new(config));
+  pub fn from_args_os<T: AsRef<OsStr>>(
+    name: &str,
+    args: Vec<T>,
+  ) -> Self {
+    let os_args = args
+      .iter()
+      .map(|arg| arg.as_ref().to_os_string())
+      .collect::<Vec<_>>();
+    let args = os_args
+      .iter()
+      .map(|arg| arg.to_string_lossy())
+      .collect::<Vec<_>>();
+    let optz = Optz::from_args(name, args);
+    Self { os_args, ..optz }
+  }
+
+  pub fn after_help(mut self, text: &str) -> Self {
+    self.after_help = Some(text.to_owned());
+    self
+  }
+
+  pub fn arg_required_else_help(mut self, required: bool) -> Self {
+    self.arg_required_else_help = required;
+    self
+  }
+
+  pub fn before_help(mut self, text: &str) -> Self {
+    self.before_help = Some(text.to_owned());
+    self
+  }
+
+  pub fn carapace_spec(&self) -> String {
+    complete::carapace(self)
+  }
+
+  pub fn color(mut self, color: ColorChoice) -> Self {
+    self.color = color;
+    self
+  }
+
+  /// Stores `config` for handlers and `Opt::default_from`. It must be
+  /// `Send + Sync` so the spec can be cloned and shared across threads
+  /// with `into_shared()`; wrap other types in `Arc<Mutex<_>>`.
+  pub fn config<T: Send + Sync + 'static>(
+    mut self,
+    config: T,
+  ) -> Self {
+    self.config = Some(Arc::new(config));
     self
   }
 
@@ -49,49 +193,183 @@ impl Optz {
     self
   }
 
+  pub fn disable_help_flag(mut self, disable: bool) -> Self {
+    self.disable_help_flag = disable;
+    self
+  }
+
+  pub fn examples(mut self, examples: &[(&str, &str)]) -> Self {
+    self.examples.extend(examples.iter().map(
+      |(command, description)| {
+        (command.to_string(), description.to_string())
+      },
+    ));
+    self
+  }
+
+  pub fn expand_env(mut self, expand_env: bool) -> Self {
+    self.expand_env = expand_env;
+    self
+  }
+
+  fn find(&self, name: &str) -> Result<&Opt> {
+    self.options.iter().find(|opt| opt.name == name).ok_or_else(
+      || {
+        OptzError::NoSuchOption(messages::fill(
+          &self.messages.no_such_option,
+          &[&name],
+        ))
+      },
+    )
+  }
+
+  pub fn fig_spec(&self) -> String {
+    complete::fig(self)
+  }
+
+  pub fn fish_completion(&self) -> String {
+    complete::fish(self)
+  }
+
+  pub fn zsh_completion(&self) -> String {
+    complete::zsh(self)
+  }
+
+  pub fn forward_to(mut self, program: &str) -> Self {
+    self.forward_to = Some(program.to_owned());
+    self
+  }
+
+  pub fn forwarded(&self) -> &[String] {
+    &self.forwarded
+  }
+
+  pub fn forward_command(&self) -> Option<Command> {
+    let program = self.forward_to.as_ref()?;
+    let mut command = Command::new(program);
+    command.args(&self.forwarded);
+    Some(command)
+  }
+
   pub fn get<T: FromStr>(&self, name: &str) -> Result<Option<T>>
   where
-    <T as FromStr>::Err: std::fmt::Debug,
+    <T as FromStr>::Err: std::fmt::Debug + 'static,
   {
-    for opt in &self.options {
-      if opt.name != name {
-        continue;
-      }
+    let opt = self.find(name)?;
+    parse_first(&self.messages, &opt.long, opt.secret, &opt.values)
+  }
 
-      if let Some(value) = opt.values.first() {
-        return Ok(Some(
-          value
-            .parse::<T>()
-            .map_err(|e| OptzError::Parse(format!("{:?}", e)))?,
-        ));
-      } else {
-        return Ok(None);
-      }
+  pub fn get_bytes(&self, name: &str) -> Option<&[u8]> {
+    let opt = self.options.iter().find(|opt| opt.name == name)?;
+    let value = opt.values.first()?;
+    let raw = (opt.source == Some(ValueSource::CommandLine))
+      .then(|| opt.indices.first())
+      .flatten()
+      .and_then(|index| raw_bytes(&self.raw_args, &self.os_args, *index, value));
+    Some(raw.unwrap_or(value.as_bytes()))
+  }
+
+  pub fn get_or<T: FromStr>(
+    &self,
+    name: &str,
+    default: T,
+  ) -> Result<T>
+  where
+    <T as FromStr>::Err: std::fmt::Debug + 'static,
+  {
+    Ok(self.get(name)?.unwrap_or(default))
+  }
+
+  pub fn get_required<T: FromStr>(&self, name: &str) -> Result<T>
+  where
+    <T as FromStr>::Err: std::fmt::Debug + 'static,
+  {
+    let long = &self.find(name)?.long;
+    self.get(name)?.ok_or_else(|| {
+      OptzError::MissingRequired(messages::fill(
+        &self.messages.required,
+        &[long],
+      ))
+    })
+  }
+
+  pub fn get_one<T: 'static>(&self, name: &str) -> Option<&T> {
+    self
+      .options
+      .iter()
+      .find(|opt| opt.name == name)
+      .and_then(|opt| opt.parsed.first())
+      .and_then(|value| value.downcast_ref::<T>())
+  }
+
+  pub fn get_path(&self, name: &str) -> Result<Option<PathBuf>> {
+    self.get_with(name, parsers::path)
+  }
+
+  pub fn get_bytesize(&self, name: &str) -> Result<Option<u64>> {
+    self.get_with(name, parsers::bytesize)
+  }
+
+  pub fn get_duration(&self, name: &str) -> Result<Option<Duration>> {
+    self.get_with(name, parsers::duration)
+  }
+
+  pub fn get_socket_addr(
+    &self,
+    name: &str,
+  ) -> Result<Option<SocketAddr>> {
+    self.get_with(name, parsers::socket_addr)
+  }
+
+  pub fn get_timestamp(
+    &self,
+    name: &str,
+  ) -> Result<Option<SystemTime>> {
+    self.get_with(name, parsers::timestamp)
+  }
+
+  fn get_with<T: Clone + 'static>(
+    &self,
+    name: &str,
+    parser: fn(&str) -> std::result::Result<T, String>,
+  ) -> Result<Option<T>> {
+    let opt = self.find(name)?;
+    if let Some(value) =
+      opt.parsed.first().and_then(|v| v.downcast_ref::<T>())
+    {
+      return Ok(Some(value.clone()));
     }
+    parse_with(&self.messages, &opt.long, opt.secret, &opt.values, parser)
+  }
 
-    Ok(None)
+  pub fn get_positional<T: FromStr>(
+    &self,
+    name: &str,
+  ) -> Result<Option<T>>
+  where
+    <T as FromStr>::Err: std::fmt::Debug + 'static,
+  {
+    self.get(name)
   }
 
-  pub fn get_values<T: FromStr>(&self, name: &str) -> Result<Vec<T>>
+  pub fn get_rest<T: FromStr>(&self) -> Result<Vec<T>>
   where
     <T as FromStr>::Err: std::fmt::Debug,
   {
-    for opt in &self.options {
-      if opt.name != name {
-        continue;
-      }
+    parse_rest(&self.messages, &self.rest)
+  }
 
-      return opt
-        .values
-        .iter()
-        .map(|s| {
-          s.parse::<T>()
-            .map_err(|e| OptzError::Parse(format!("{:?}", e)))
-        })
-        .collect::<Result<Vec<T>>>();
-    }
+  pub fn get_values<T: FromStr>(&self, name: &str) -> Result<Vec<T>>
+  where
+    <T as FromStr>::Err: std::fmt::Debug + 'static,
+  {
+    let opt = self.find(name)?;
+    parse_all(&self.messages, &opt.long, opt.secret, &opt.values)
+  }
 
-    Ok(vec![])
+  pub fn group(mut self, group: Group) -> Self {
+    self.groups.push(group);
+    self
   }
 
   pub fn handler(mut self, handler: fn(&Optz) -> Result<()>) -> Self {
@@ -103,198 +381,3128 @@ impl Optz {
     Ok(self.get::<bool>(name)?.unwrap_or(false))
   }
 
-  fn help(self: &Self) -> Result<()> {
-    if let Some(usage) = &self.usage {
-      println!("{}", usage);
+  pub fn help_flag_names(mut self, names: &[&str]) -> Self {
+    self.help_flag_names =
+      names.iter().map(|n| n.to_string()).collect();
+    self
+  }
+
+  pub fn help_template(mut self, template: &str) -> Self {
+    self.help_template = Some(template.to_owned());
+    self
+  }
+
+  pub fn help_translator(
+    mut self,
+    translator: fn(&str) -> String,
+  ) -> Self {
+    self.help_translator = Some(translator);
+    self
+  }
+
+  fn help(&self) -> Result<()> {
+    let short = self
+      .options
+      .iter()
+      .find(|opt| opt.name == "help")
+      .is_some_and(|opt| {
+        opt
+          .indices
+          .iter()
+          .all(|index| opt.short.as_ref() == self.args.get(index - 1))
+      });
+    if self.return_help {
+      let help = self.render_help_for(self.plain_color(), !short);
+      return Err(OptzError::DisplayHelp(help));
     }
-    for opt in self.options.iter() {
-      let mut res = "  ".to_owned();
-      if let Some(short) = &opt.short {
-        res.push_str(short);
-        res.push_str(", ");
-      } else {
-        res.push_str("    ");
-      }
-      res.push_str(format!("{:<12} ", opt.long).as_str());
-      if let Some(desc) = &opt.description {
-        res.push_str(desc);
-      }
-      println!("{}", res);
+    let mut stdout = io::stdout();
+    let color = self.color.enabled(stdout.is_terminal());
+    let help = self.render_help_for(color, !short);
+    let paged = self.pages_help(&help, stdout.is_terminal())
+      && page(&help).is_ok();
+    if !paged {
+      let _ = stdout.write_all(help.as_bytes());
     }
     std::process::exit(0);
   }
 
-  pub fn option(mut self, opt: Opt) -> Self {
-    self.options.push(opt);
-    self
+  // Only a terminal is paged, so the terminal height is never looked
+  // up for redirected output.
+  pub(crate) fn pages_help(
+    &self,
+    help: &str,
+    is_terminal: bool,
+  ) -> bool {
+    self.paged_help
+      && is_terminal
+      && help.lines().count() > terminal_height()
   }
 
-  pub fn parse(mut self) -> Result<Self> {
-    if self.usage.is_none() {
-      self.usage = Some(format!("Usage: {} [options]", self.name));
-    }
+  pub fn render_help(&self) -> String {
+    self.render_help_for(self.plain_color(), false)
+  }
 
-    self.options.push(
-      Opt::flag("help")
-        .description("Show help")
-        .short("-h")
-        .handler(Self::help),
-    );
+  pub fn render_long_help(&self) -> String {
+    self.render_help_for(self.plain_color(), true)
+  }
 
-    let mut args_iter = self.args.iter().peekable();
-    while let Some(arg) = args_iter.next() {
-      if arg == "-" {
-        continue;
-      }
-      if arg.starts_with("-") {
-        for opt in self.options.iter_mut() {
-          if &opt.long == arg || opt.short == Some(arg.clone()) {
-            match opt.arg {
-              Arg::Flag => {
-                if opt.multiple {
-                  opt.values.push("true".to_string());
-                } else {
-                  opt.values = vec!["true".to_string()];
-                }
-              }
-              Arg::Arg => {
-                let next_arg = args_iter.next();
-                match next_arg {
-                  Some(arg) => {
-                    if opt.multiple {
-                      opt.values.push(arg.clone());
-                    } else {
-                      opt.values = vec![arg.clone()];
-                    }
-                  }
-                  None => {
-                    return Err(OptzError::MissingArgument);
-                  }
-                }
-              }
-            }
-            break;
-          }
-        }
-      } else {
-        self.rest.push(arg.clone());
-      }
-    }
+  // Rendered help ignores the terminal and the environment; only an
+  // explicit ColorChoice::Always colours it.
+  fn plain_color(&self) -> bool {
+    self.color == ColorChoice::Always
+  }
 
-    for opt in self.options.iter() {
-      if !opt.values.is_empty() {
-        if let Some(handler) = opt.handler {
-          let res = handler(&self);
-          if !res.is_ok() {
-            return Err(OptzError::Parse(
-              res.unwrap_err().to_string(),
-            ));
-          }
-        }
-      }
-    }
+  fn render_help_for(&self, color: bool, long: bool) -> String {
+    let mut optz = self.clone();
+    optz.prepare();
+    help::render(&optz, color, long)
+  }
 
-    if let Some(handler) = self.handler {
-      let res = handler(&self);
-      if !res.is_ok() {
-        return Err(OptzError::Parse(res.unwrap_err().to_string()));
-      }
-    }
+  pub fn write_help<W: Write>(&self, w: &mut W) -> io::Result<()> {
+    w.write_all(self.render_help().as_bytes())
+  }
 
-    Ok(self)
+  pub fn hide_annotations(mut self, hide_annotations: bool) -> Self {
+    self.hide_annotations = hide_annotations;
+    self
   }
 
-  pub fn usage<S: Into<String>>(mut self, text: S) -> Self {
-    self.usage = Some(text.into());
+  pub fn hide_authors(mut self, hide_authors: bool) -> Self {
+    self.hide_authors = hide_authors;
     self
   }
-}
 
-impl IntoIterator for Optz {
-  type Item = Opt;
-  type IntoIter = std::vec::IntoIter<Self::Item>;
+  pub fn hide_description(mut self, hide_description: bool) -> Self {
+    self.hide_description = hide_description;
+    self
+  }
 
-  fn into_iter(self) -> Self::IntoIter {
-    self.options.into_iter()
+  pub fn indices_of(&self, name: &str) -> Vec<usize> {
+    self
+      .options
+      .iter()
+      .find(|opt| opt.name == name)
+      .map_or_else(Vec::new, |opt| opt.indices.clone())
   }
-}
 
-#[derive(Clone, Debug, Default)]
-pub enum Arg {
-  Arg,
-  #[default]
-  Flag,
-}
+  pub fn into_shared(mut self) -> Arc<Self> {
+    self.prepare();
+    Arc::new(self)
+  }
 
-#[derive(Clone, Default)]
-pub struct Opt {
-  pub arg: Arg,
-  pub description: Option<String>,
-  pub handler: Option<fn(&Optz) -> Result<()>>,
-  pub long: String,
-  pub multiple: bool,
-  pub name: String,
-  pub short: Option<String>,
-  pub values: Vec<String>,
-}
+  pub fn is_present(&self, name: &str) -> bool {
+    self
+      .options
+      .iter()
+      .any(|opt| opt.name == name && opt.given())
+  }
 
-impl Opt {
-  pub fn flag(name: &str) -> Self {
-    let long = format!("--{}", name);
-    Self {
-      arg: Arg::Flag,
-      name: name.to_owned(),
-      long: long,
-      ..Default::default()
-    }
+  pub fn lenient(mut self, lenient: bool) -> Self {
+    self.lenient = lenient;
+    self
   }
 
-  pub fn arg(name: &str) -> Self {
-    let long = format!("--{}", name);
-    Self {
-      arg: Arg::Arg,
-      name: name.to_owned(),
-      long: long,
-      ..Default::default()
-    }
+  pub fn lint(&self) -> Vec<LintWarning> {
+    lint::lint(self)
   }
 
-  pub fn multiple(mut self, multiple: bool) -> Self {
-    self.multiple = multiple;
+  pub fn long_about(mut self, text: &str) -> Self {
+    self.long_about = help::parse_sections(text);
     self
   }
 
-  pub fn default_value(mut self, value: &str) -> Self {
-    self.values = vec![value.to_owned()];
+  pub fn matched(&self) -> &[Matched] {
+    &self.matched
+  }
+
+  pub fn messages(mut self, messages: Messages) -> Self {
+    self.messages = messages;
     self
   }
 
-  pub fn description(mut self, desc: &str) -> Self {
-    self.description = Some(desc.into());
+  pub fn metadata(mut self, metadata: Metadata) -> Self {
+    self.name = metadata.name;
+    self.version = metadata.version;
+    self.description = metadata.description;
     self
   }
 
-  pub fn handler(mut self, handler: fn(&Optz) -> Result<()>) -> Self {
-    self.handler = Some(handler);
+  pub fn occurrences(&self, name: &str) -> usize {
     self
+      .options
+      .iter()
+      .find(|opt| opt.name == name)
+      .map_or(0, |opt| opt.occurrences)
   }
 
-  pub fn short(mut self, short: &str) -> Self {
-    self.short = Some(short.into());
+  pub fn option(mut self, opt: Opt) -> Self {
+    self.options.push(opt);
+    self
+  }
+
+  pub fn options<I: IntoIterator<Item = Opt>>(
+    mut self,
+    opts: I,
+  ) -> Self {
+    self.options.extend(opts);
+    self
+  }
+
+  pub(crate) fn prepare(&mut self) {
+    if let Some(prefix) = &self.env_prefix {
+      for opt in self.options.iter_mut().filter(|o| !o.skip_env) {
+        if opt.env.is_none() {
+          opt.env = Some(format!(
+            "{}_{}",
+            prefix,
+            opt.name.to_uppercase().replace('-', "_")
+          ));
+        }
+      }
+    }
+
+    if self.version.is_some()
+      && !self.options.iter().any(|opt| opt.name == "version")
+    {
+      let mut opt = Opt::flag("version")
+        .description(&self.messages.show_version)
+        .handler(Self::print_version)
+        .skip_env(true);
+      if !self
+        .options
+        .iter()
+        .any(|o| o.short.as_deref() == Some("-V"))
+      {
+        opt = opt.short("-V");
+      }
+      self.options.push(opt);
+    }
+
+    if self.config_flag
+      && !self.options.iter().any(|opt| opt.name == "config")
+    {
+      self.options.push(
+        Opt::arg("config")
+          .value_name("path")
+          .description(&self.messages.config_file)
+          .skip_env(true),
+      );
+    }
+
+    if self.subcommands.iter().any(|sub| sub.experimental)
+      && !self.options.iter().any(|o| o.name == "enable-experimental")
+    {
+      self.options.push(
+        Opt::flag("enable-experimental")
+          .description(&self.messages.enable_experimental)
+          .global(true)
+          .skip_env(true),
+      );
+    }
+
+    if !self.disable_help_flag
+      && !self.options.iter().any(|opt| opt.name == "help")
+    {
+      let mut opt = Opt::flag("help")
+        .description(&self.messages.show_help)
+        .handler(Self::help)
+        .skip_env(true);
+      let taken = |short: &str| {
+        self
+          .options
+          .iter()
+          .any(|o| o.short.as_deref() == Some(short))
+      };
+      if self.help_flag_names.is_empty() {
+        if !taken("-h") {
+          opt = opt.short("-h");
+        }
+      } else {
+        let mut names = self.help_flag_names.clone();
+        if let Some(i) =
+          names.iter().position(|n| n.starts_with("--"))
+        {
+          opt.long = names.remove(i);
+        }
+        if let Some(i) =
+          names.iter().position(|n| !n.starts_with("--"))
+        {
+          opt = opt.short(&names.remove(i));
+        }
+        for name in names {
+          opt = opt.alias(&name);
+        }
+      }
+      self.options.push(opt);
+    }
+
+    // After the built-ins, so the synopsis lists `--config` too.
+    if self.usage.is_none() {
+      self.usage =
+        Some(format!("{} {}", self.messages.usage, self.synopsis()));
+    }
+  }
+
+  fn synopsis(&self) -> String {
+    let mut words = vec![self.full_name()];
+    for opt in &self.options {
+      if let Arg::Positional = opt.arg {
+        continue;
+      }
+      if opt.hidden || opt.name == "help" || opt.name == "version" {
+        continue;
+      }
+      let mut word = opt.short.clone().unwrap_or(opt.long.clone());
+      if let Arg::Arg = opt.arg {
+        word = format!("{} {}", opt.long, opt.placeholder());
+      }
+      if !opt.required {
+        word = format!("[{}]", word);
+      }
+      if opt.multiple {
+        word.push_str("...");
+      }
+      words.push(word);
+    }
+    let positionals = self.positionals();
+    for (i, opt) in positionals.iter().enumerate() {
+      let dots = if opt.many.is_some() { "..." } else { "" };
+      if dots == "..." && i + 1 == positionals.len() {
+        words.push("[--]".to_string());
+      }
+      let min = opt.many.map_or(0, |(min, _)| min);
+      if opt.required || min > 0 {
+        words.push(format!("{}{}", opt.long, dots));
+      } else {
+        words.push(format!("[{}]{}", opt.long, dots));
+      }
+    }
+    if self.subcommand_required {
+      words.push("<command>".to_string());
+    } else if !self.subcommands.is_empty() {
+      words.push("[<command>]".to_string());
+    }
+    if self.forward_to.is_some() {
+      words.push("-- <args>...".to_string());
+    }
+    words.join(" ")
+  }
+
+  pub fn paged_help(mut self, paged_help: bool) -> Self {
+    self.paged_help = paged_help;
     self
   }
+
+  pub fn parse(mut self) -> Result<Matches> {
+    if self.arg_required_else_help
+      && self.default_subcommand.is_none()
+      && self.args.is_empty()
+    {
+      self.prepare();
+      self.help()?;
+    }
+    if self.resolve(true, &[])? {
+      self.short_circuit()?;
+    } else {
+      self.run_handlers()?;
+    }
+    Ok(Matches::from(self))
+  }
+
+  pub fn dispatch(self) -> Result<()> {
+    self.dispatch_with(|e| e)
+  }
+
+  // `run` reports a failing handler as `OptzError::Parse`, as
+  // `parse()` does, so it exits with status 1.
+  pub(crate) fn dispatch_with(
+    mut self,
+    map_err: fn(OptzError) -> OptzError,
+  ) -> Result<()> {
+    if self.arg_required_else_help
+      && self.default_subcommand.is_none()
+      && self.args.is_empty()
+    {
+      self.prepare();
+      self.help()?;
+    }
+    if self.resolve(true, &[])? {
+      return self.short_circuit();
+    }
+    let mut level = &self;
+    let mut target = None;
+    loop {
+      level.run_option_handlers()?;
+      if let Some(handler) = level.handler {
+        target = Some((level, handler));
+      }
+      match &level.subcommand_matches {
+        Some(sub) => level = sub,
+        None => break,
+      }
+    }
+    match target {
+      Some((optz, handler)) => handler(optz).map_err(map_err),
+      None => Ok(()),
+    }
+  }
+
+  fn short_circuit(&self) -> Result<()> {
+    if let Some(sub) = &self.subcommand_matches {
+      return sub.short_circuit();
+    }
+    let opt = self.options.iter().find(|opt| {
+      opt.given() && matches!(opt.name.as_str(), "help" | "version")
+    });
+    if let Some(handler) = opt.and_then(|opt| opt.handler) {
+      handler(self)?;
+    }
+    Ok(())
+  }
+
+  fn run_handlers(&self) -> Result<()> {
+    self.run_option_handlers()?;
+
+    if let Some(handler) = self.handler
+      && let Err(e) = handler(self)
+    {
+      return Err(OptzError::Parse(e.to_string()));
+    }
+
+    if let Some(sub) = &self.subcommand_matches {
+      sub.run_handlers()?;
+    }
+    Ok(())
+  }
+
+  fn run_option_handlers(&self) -> Result<()> {
+    for opt in self.options.iter() {
+      if opt.is_set()
+        && let Some(handler) = opt.handler
+        && let Err(e) = handler(self)
+      {
+        return Err(OptzError::Parse(e.to_string()));
+      }
+    }
+    Ok(())
+  }
+
+  pub fn validate_only<T: AsRef<str>>(
+    &self,
+    args: Vec<T>,
+  ) -> Result<()> {
+    self.with_args(args).resolve(false, &[]).map(|_| ())
+  }
+
+  fn resolve(
+    &mut self,
+    warn: bool,
+    inherited: &[Opt],
+  ) -> Result<bool> {
+    if self
+      .resolve_args(warn, inherited)
+      .map_err(|e| self.in_context(e))?
+    {
+      return Ok(true);
+    }
+    let globals = self
+      .options
+      .iter()
+      .filter(|opt| opt.global)
+      .cloned()
+      .collect::<Vec<_>>();
+    let Some(sub) = self.subcommand_matches.as_deref_mut() else {
+      return Ok(false);
+    };
+    sub.config_table = self
+      .config_table
+      .as_ref()
+      .and_then(|table| table.get(&sub.name))
+      .cloned();
+    let short_circuit = sub.resolve(warn, &globals)?;
+    for opt in self.options.iter_mut().filter(|opt| opt.global) {
+      if let Some(inner) =
+        sub.options.iter().find(|o| o.name == opt.name)
+        && inner.occurrences > 0
+      {
+        opt.copy_values(inner);
+        opt.indices =
+          inner.indices.iter().map(|i| i + sub.arg_offset).collect();
+      }
+    }
+    if let Some(sub) = &self.subcommand_matches
+      && sub.experimental
+      && !short_circuit
+      && !self.experimental_enabled()
+    {
+      return Err(OptzError::Conflict(messages::fill(
+        &self.messages.experimental_command,
+        &[&sub.full_name(), &self.experimental_env()],
+      )));
+    }
+    Ok(short_circuit)
+  }
+
+  fn experimental_enabled(&self) -> bool {
+    self
+      .options
+      .iter()
+      .any(|opt| opt.name == "enable-experimental" && opt.given())
+      || env::var_os(self.experimental_env())
+        .is_some_and(|v| !v.is_empty() && v != "0")
+  }
+
+  fn experimental_env(&self) -> String {
+    let root = self.command_path.first().unwrap_or(&self.name);
+    format!(
+      "{}_ENABLE_EXPERIMENTAL",
+      root.to_uppercase().replace('-', "_")
+    )
+  }
+
+  fn in_context(&self, e: OptzError) -> OptzError {
+    if self.command_path.is_empty() {
+      return e;
+    }
+    let mut path = self.command_path[1..].to_vec();
+    path.push(self.name.clone());
+    e.map_message(|msg| format!("{}: {}", path.join(" "), msg))
+  }
+
+  fn resolve_args(
+    &mut self,
+    warn: bool,
+    inherited: &[Opt],
+  ) -> Result<bool> {
+    self.prepare();
+
+    self.rest.clear();
+    self.forwarded.clear();
+    self.matched.clear();
+    self.subcommand_matches = None;
+    self.external_subcommand = None;
+    for opt in self.options.iter_mut() {
+      opt.values.clear();
+      opt.indices.clear();
+      opt.parsed.clear();
+      opt.occurrences = 0;
+      opt.source = None;
+    }
+    let mut rest_indices = Vec::new();
+    let mut warnings = Vec::new();
+    let tokens = match self.multicall_subcommand() {
+      Some(sub) => {
+        self.subcommand_matches = Some(Box::new(sub));
+        Vec::new()
+      }
+      None => self.tokenize(),
+    };
+    let mut args_iter =
+      tokens.iter().map(|(arg, index)| (arg, *index)).peekable();
+    while let Some((arg, index)) = args_iter.next() {
+      if arg == "-" {
+        continue;
+      }
+      if arg == "--" {
+        if self.forward_to.is_some() {
+          self.forwarded =
+            args_iter.by_ref().map(|(arg, _)| arg.clone()).collect();
+        } else {
+          for (arg, index) in args_iter.by_ref() {
+            self.rest.push(RestArg::Positional(arg.clone()));
+            rest_indices.push(index);
+            self.matched.push(Matched::Positional {
+              value: arg.clone(),
+              index,
+            });
+          }
+        }
+        break;
+      }
+      let dos_arg = match arg.strip_prefix('/') {
+        Some(name) if self.slash_options && !name.is_empty() => {
+          let token = format!("--{}", name);
+          let key = token
+            .split(|c| c == '=' || self.value_separators.contains(&c))
+            .next()
+            .unwrap_or_default();
+          self
+            .options
+            .iter()
+            .any(|opt| opt.matches(key))
+            .then_some(token)
+        }
+        _ => None,
+      };
+      let token = dos_arg.as_deref().unwrap_or(arg);
+      if token.starts_with("-") {
+        let split = token.char_indices().find(|(_, c)| {
+          *c == '=' || self.value_separators.contains(c)
+        });
+        let (key, attached) = match split {
+          Some((i, c)) => {
+            (&token[..i], Some(&token[i + c.len_utf8()..]))
+          }
+          None => (token, None),
+        };
+        let matched =
+          self.options.iter_mut().find(|opt| opt.matches(key));
+        let Some(opt) = matched else {
+          if self.strict {
+            return Err(unknown_option(&self.messages, arg));
+          }
+          if self.lenient {
+            self.rest.push(RestArg::UnknownOption(arg.clone()));
+            rest_indices.push(index);
+          }
+          continue;
+        };
+        if warn && opt.renamed_from.iter().any(|old| old == key) {
+          warn_once(
+            &mut warnings,
+            messages::fill(
+              &self.messages.renamed_warning,
+              &[&key, &opt.long],
+            ),
+          );
+        }
+        let mut value_index = index;
+        let value = match opt.arg {
+          Arg::Flag => match attached {
+            Some(value) => parsers::boolean(value)
+              .map_err(|e| {
+                opt.invalid_value(&self.messages, value, &e)
+              })?
+              .to_string(),
+            None => "true".to_string(),
+          },
+          Arg::Positional => {
+            unreachable!("positionals match by index")
+          }
+          Arg::Arg => match attached {
+            Some(value) => value.to_string(),
+            None if opt.require_equals => {
+              return Err(OptzError::InvalidValue(messages::fill(
+                &self.messages.require_equals,
+                &[&opt.long, &opt.long, &opt.placeholder()],
+              )));
+            }
+            None => match args_iter.next() {
+              Some((next, next_index)) => {
+                value_index = next_index;
+                next.clone()
+              }
+              None => {
+                return Err(OptzError::MissingArgument(
+                  self.messages.missing_argument.clone(),
+                ));
+              }
+            },
+          },
+        };
+        opt.occurrences += 1;
+        self.matched.push(Matched::Option {
+          name: opt.name.clone(),
+          value: value.clone(),
+          index,
+        });
+        opt.push_value(value, value_index);
+        if let Arg::Flag = opt.arg
+          && matches!(opt.name.as_str(), "help" | "version")
+          && opt.handler.is_some()
+          && opt.given()
+        {
+          return Ok(true);
+        }
+      } else if arg == "help"
+        && self.help_subcommand()
+        && !self.has_positionals()
+        && let Some(help) =
+          self.options.iter().find(|o| o.name == "help")
+      {
+        let mut args =
+          args_iter.map(|(arg, _)| arg.clone()).collect::<Vec<_>>();
+        let mut spec: &Optz = self;
+        let names = args.iter().take_while(|arg| !arg.starts_with('-'));
+        for name in names {
+          if spec.subcommands.is_empty()
+            || spec.allow_external_subcommands
+          {
+            break;
+          }
+          match spec.find_subcommand(name)? {
+            Some(sub) => spec = sub,
+            None => return Err(spec.unknown_command(name)),
+          }
+        }
+        args.push(help.long.clone());
+        self.args = args;
+        return self.resolve_args(warn, inherited);
+      } else if let Some(spec) = self.find_subcommand(arg)? {
+        if warn && spec.renamed_from.iter().any(|old| old == arg) {
+          warn_once(
+            &mut warnings,
+            messages::fill(
+              &self.messages.command_renamed_warning,
+              &[arg, &spec.name],
+            ),
+          );
+        }
+        let args = args_iter.by_ref().map(|(arg, _)| arg.clone());
+        let sub =
+          self.enter_subcommand(spec, arg, index, args.collect());
+        self.subcommand_matches = Some(Box::new(sub));
+        break;
+      } else if let Some(e) = self.unknown_subcommand(arg) {
+        return Err(e);
+      } else if self.allow_external_subcommands
+        && !self.has_positionals()
+      {
+        let args = args_iter.by_ref().map(|(arg, _)| arg.clone());
+        self.external_subcommand =
+          Some((arg.clone(), args.collect()));
+        break;
+      } else {
+        self.rest.push(RestArg::Positional(arg.clone()));
+        rest_indices.push(index);
+        self.matched.push(Matched::Positional {
+          value: arg.clone(),
+          index,
+        });
+      }
+    }
+
+    self.assign_positionals(&mut rest_indices);
+
+    for opt in
+      self.options.iter().filter(|o| warn && o.occurrences > 0)
+    {
+      if let Some(note) = &opt.deprecated {
+        eprintln!(
+          "{}",
+          messages::fill(
+            &self.messages.deprecated_warning,
+            &[&opt.long, note]
+          )
+        );
+      }
+    }
+
+    for opt in self.options.iter_mut().filter(|o| o.occurrences == 0)
+    {
+      if let Some(parent) =
+        inherited.iter().find(|o| o.name == opt.name)
+      {
+        opt.copy_values(parent);
+      }
+    }
+
+    for opt in self.options.iter_mut() {
+      opt.apply_env(&self.messages)?;
+    }
+    self.load_config()?;
+    self.apply_config()?;
+    self.apply_defaults();
+
+    if self.expand_env {
+      for opt in self.options.iter_mut().filter(|o| !o.literal) {
+        opt.values = opt
+          .values
+          .iter()
+          .map(|value| {
+            parsers::expand_env(value).map_err(|e| {
+              opt.invalid_value(&self.messages, value, &e)
+            })
+          })
+          .collect::<Result<Vec<_>>>()?;
+      }
+    }
+
+    for opt in self.options.iter_mut() {
+      opt.resolve_possible_values(&self.messages)?;
+      opt.validate(&self.messages)?;
+      opt.parse_values(&self.messages)?;
+    }
+
+    self.check_constraints()?;
+
+    if self.subcommand_matches.is_none()
+      && self.external_subcommand.is_none()
+    {
+      match &self.default_subcommand {
+        Some(name) if !self.has_positionals() => {
+          let spec = self
+            .subcommands
+            .iter()
+            .find(|sub| &sub.name == name)
+            .ok_or_else(|| {
+              OptzError::NoSuchOption(messages::fill(
+                &self.messages.no_such_command,
+                &[name],
+              ))
+            })?;
+          let sub = self.enter_subcommand(
+            spec,
+            name,
+            self.args.len(),
+            Vec::new(),
+          );
+          self.subcommand_matches = Some(Box::new(sub));
+        }
+        _ if self.subcommand_required => {
+          let names = self
+            .subcommands
+            .iter()
+            .map(|sub| sub.name.as_str())
+            .collect::<Vec<_>>()
+            .join(", ");
+          let mut msg = messages::fill(
+            &self.messages.command_required,
+            &[&names],
+          );
+          if let Some((opt, value)) = self.swallowed_subcommand() {
+            msg.push_str(&messages::fill(
+              &self.messages.swallowed_command,
+              &[&opt, &value],
+            ));
+          }
+          msg.push('\n');
+          msg.push_str(self.usage.as_deref().unwrap_or_default());
+          return Err(OptzError::MissingRequired(msg));
+        }
+        _ => {}
+      }
+    }
+    Ok(false)
+  }
+
+  // `offset` is where the subcommand's name sits in `raw_args`; the
+  // arguments after it are `args`.
+  fn enter_subcommand(
+    &self,
+    spec: &Optz,
+    name: &str,
+    offset: usize,
+    args: Vec<String>,
+  ) -> Optz {
+    let mut sub = spec.clone();
+    sub.arg_offset = offset;
+    sub.command_path = self.command_path.clone();
+    sub.command_path.push(self.name.clone());
+    sub.return_help |= self.return_help;
+    if sub.env_prefix.is_none() {
+      sub.env_prefix.clone_from(&self.env_prefix);
+    }
+    if self.propagate_version {
+      sub.propagate_version = true;
+      if sub.version.is_none() {
+        sub.version.clone_from(&self.version);
+      }
+    }
+    for opt in self.options.iter().filter(|opt| opt.global) {
+      if !sub.options.iter().any(|o| o.name == opt.name) {
+        sub.options.push(opt.clone());
+      }
+    }
+    sub.os_args =
+      self.os_args.get(offset..).unwrap_or_default().to_vec();
+    sub.raw_args = vec![name.to_string()];
+    sub.raw_args.extend(args);
+    sub.args = sub.raw_args[1..].to_vec();
+    sub
+  }
+
+  // A subcommand spec as it would be parsed below this command, with
+  // inherited settings, globals and built-in options in place.
+  pub(crate) fn prepared_subcommand(&self, spec: &Optz) -> Optz {
+    let mut sub =
+      self.enter_subcommand(spec, &spec.name, 0, Vec::new());
+    sub.prepare();
+    sub
+  }
+
+  fn tokenize(&self) -> Vec<(String, usize)> {
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < self.args.len() {
+      if self.args[i] == "--" {
+        tokens.extend(self.args[i..].iter().cloned().zip(i + 1..));
+        break;
+      }
+      let claimed = self
+        .tokenizers
+        .iter()
+        .find_map(|tokenizer| tokenizer(&self.args[i..]))
+        .filter(|(consumed, _)| *consumed > 0);
+      match claimed {
+        Some((consumed, replacement)) => {
+          tokens.extend(replacement.into_iter().map(|t| (t, i + 1)));
+          i += consumed;
+        }
+        None => {
+          tokens.push((self.args[i].clone(), i + 1));
+          i += 1;
+        }
+      }
+    }
+    tokens
+  }
+
+  // Resolves without running handlers or exiting, so one spec can
+  // parse many command lines; help and version come back as errors.
+  pub fn parse_from<T: AsRef<str>>(
+    &self,
+    args: Vec<T>,
+  ) -> Result<Matches> {
+    let mut optz = self.with_args(args).return_help(true);
+    if optz.arg_required_else_help
+      && optz.default_subcommand.is_none()
+      && optz.args.is_empty()
+    {
+      optz.prepare();
+      optz.help()?;
+    }
+    if optz.resolve(true, &[])? {
+      optz.short_circuit()?;
+    }
+    Ok(Matches::from(optz))
+  }
+
+  fn with_args<T: AsRef<str>>(&self, args: Vec<T>) -> Self {
+    let Optz { args, raw_args, .. } =
+      Optz::from_args(&self.name, args);
+    Optz {
+      args,
+      raw_args,
+      os_args: Vec::new(),
+      ..self.clone()
+    }
+  }
+
+  fn check_constraints(&self) -> Result<()> {
+    let m = &self.messages;
+    for opt in self.options.iter().filter(|o| o.required) {
+      let deferred = opt.global && self.subcommand_matches.is_some();
+      if opt.values.is_empty() && !deferred {
+        return Err(OptzError::MissingRequired(messages::fill(
+          &m.required,
+          &[&opt.long],
+        )));
+      }
+    }
+    if let Some(program) = &self.forward_to
+      && self.forwarded.is_empty()
+    {
+      return Err(OptzError::MissingRequired(messages::fill(
+        &m.forwarded_missing,
+        &[program],
+      )));
+    }
+    let present = |name: &str| {
+      self
+        .options
+        .iter()
+        .find(|opt| opt.name == name && opt.given())
+    };
+    for opt in self.options.iter() {
+      let Some((min, max)) = opt.many else {
+        continue;
+      };
+      let count = opt.values.len();
+      if count < min {
+        return Err(OptzError::MissingRequired(messages::fill(
+          &m.too_few_values,
+          &[&opt.long, &min, &count],
+        )));
+      }
+      if let Some(max) = max
+        && count > max
+      {
+        return Err(OptzError::InvalidValue(messages::fill(
+          &m.too_many_values,
+          &[&opt.long, &max, &count],
+        )));
+      }
+    }
+    for opt in self.options.iter().filter(|o| o.exclusive) {
+      let others =
+        self.options.iter().any(|o| o.name != opt.name && o.given());
+      if opt.given() && (others || !self.rest.is_empty()) {
+        return Err(OptzError::Conflict(messages::fill(
+          &m.exclusive,
+          &[&opt.long],
+        )));
+      }
+    }
+    for opt in self.options.iter().filter(|o| o.given()) {
+      for conflict in &opt.conflicts_with {
+        if let Some(other) = present(conflict) {
+          return Err(OptzError::Conflict(messages::fill(
+            &m.conflict,
+            &[&opt.long, &other.long],
+          )));
+        }
+      }
+      for required in &opt.requires {
+        let satisfied = self
+          .options
+          .iter()
+          .any(|o| &o.name == required && o.is_set());
+        if !satisfied {
+          return Err(OptzError::MissingRequired(messages::fill(
+            &m.requires,
+            &[&opt.long, &format!("--{}", required)],
+          )));
+        }
+      }
+    }
+    for opt in self.options.iter().filter(|o| o.values.is_empty()) {
+      for (other, value) in &opt.required_if_eq {
+        let triggered = self
+          .options
+          .iter()
+          .any(|o| &o.name == other && o.values.contains(value));
+        if triggered {
+          return Err(OptzError::MissingRequired(messages::fill(
+            &m.required_if,
+            &[&opt.long, &format!("--{}", other), value],
+          )));
+        }
+      }
+    }
+    for group in self.groups.iter().filter(|g| g.required) {
+      let satisfied = self
+        .options
+        .iter()
+        .any(|o| group.args.contains(&o.name) && o.is_set());
+      if !satisfied {
+        let alternatives = group
+          .args
+          .iter()
+          .map(|name| format!("--{}", name))
+          .collect::<Vec<_>>();
+        return Err(OptzError::MissingRequired(messages::fill(
+          &m.group_required,
+          &[&alternatives.join(", "), &group.name],
+        )));
+      }
+    }
+    for group in self.groups.iter().filter(|g| g.exclusive) {
+      let given = group
+        .args
+        .iter()
+        .filter_map(|name| present(name))
+        .collect::<Vec<_>>();
+      if let [first, second, ..] = given.as_slice() {
+        return Err(OptzError::Conflict(messages::fill(
+          &m.group_conflict,
+          &[&first.long, &second.long, &group.name],
+        )));
+      }
+    }
+    Ok(())
+  }
+
+  pub fn positionals(&self) -> Vec<&Opt> {
+    let mut positionals = self
+      .options
+      .iter()
+      .filter(|opt| matches!(opt.arg, Arg::Positional))
+      .collect::<Vec<_>>();
+    positionals.sort_by_key(|opt| opt.index.unwrap_or(usize::MAX));
+    positionals
+  }
+
+  fn assign_positionals(&mut self, rest_indices: &mut Vec<usize>) {
+    let names = self
+      .positionals()
+      .iter()
+      .map(|opt| opt.name.clone())
+      .collect::<Vec<_>>();
+    for name in names {
+      let Some(opt) =
+        self.options.iter_mut().find(|o| o.name == name)
+      else {
+        continue;
+      };
+      while let Some(pos) = self
+        .rest
+        .iter()
+        .position(|arg| matches!(arg, RestArg::Positional(_)))
+      {
+        let value = self.rest.remove(pos).as_str().to_string();
+        let index = rest_indices.remove(pos);
+        opt.occurrences += 1;
+        opt.push_value(value, index);
+        if opt.many.is_none() {
+          break;
+        }
+      }
+    }
+  }
+
+  fn apply_defaults(&mut self) {
+    let unset = self
+      .options
+      .iter()
+      .map(|opt| opt.values.is_empty())
+      .collect::<Vec<_>>();
+    let config = self.config.as_deref();
+    for opt in self.options.iter_mut().filter(|o| o.values.is_empty())
+    {
+      let default = opt
+        .default_value
+        .clone()
+        .or_else(|| opt.default_value_fn.map(|f| f()))
+        .or_else(|| {
+          opt.default_from.as_ref().and_then(|f| f(config?))
+        });
+      if let Some(default) = default {
+        opt.values = vec![default];
+        opt.source = Some(ValueSource::Default);
+      }
+    }
+    // Conditions see the plain defaults resolved above, so one option's
+    // default can trigger another's conditional default.
+    let conditional = self
+      .options
+      .iter()
+      .zip(&unset)
+      .map(|(opt, unset)| {
+        opt
+          .default_value_ifs
+          .iter()
+          .filter(|_| *unset)
+          .find(|(other, value, _)| {
+            self.options.iter().any(|o| {
+              &o.name == other
+                && match value {
+                  Some(value) => o.values.contains(value),
+                  None => !o.values.is_empty(),
+                }
+            })
+          })
+          .map(|(_, _, default)| default.clone())
+      })
+      .collect::<Vec<_>>();
+    for (opt, default) in self.options.iter_mut().zip(conditional) {
+      if let Some(default) = default {
+        opt.values = vec![default];
+        opt.source = Some(ValueSource::Default);
+      }
+    }
+  }
+
+  pub fn raw_args(&self) -> &[String] {
+    &self.raw_args
+  }
+
+  fn attached_at(&self, arg: &str) -> Option<usize> {
+    let is_option = arg.starts_with('-')
+      || (self.slash_options && arg.starts_with('/'));
+    if !is_option {
+      return None;
+    }
+    arg.find(|c| c == '=' || self.value_separators.contains(&c))
+  }
+
+  fn redacted_args(&self) -> Vec<String> {
+    let mut args = self.args.clone();
+    for opt in self.options.iter().filter(|o| o.secret) {
+      for index in &opt.indices {
+        let Some(arg) = args.get_mut(index - 1) else {
+          continue;
+        };
+        *arg = match self.attached_at(arg) {
+          Some(i) => format!("{}{}", &arg[..=i], REDACTED),
+          None => REDACTED.to_string(),
+        };
+      }
+    }
+    // The subcommand redacts its own secrets, including globals given
+    // after its name.
+    if let Some(sub) = &self.subcommand_matches {
+      args.truncate(sub.arg_offset);
+      args.extend(sub.redacted_args());
+    }
+    args
+  }
+
+  pub fn redacted_invocation(&self) -> String {
+    let mut words = vec![self.name.clone()];
+    for arg in self.redacted_args() {
+      let start = match self.attached_at(&arg) {
+        Some(i) => i + 1,
+        None if arg.starts_with('-') => arg.len(),
+        None => 0,
+      };
+      let (key, value) = arg.split_at(start);
+      let path_like =
+        value.contains(['/', '\\']) || value.starts_with('~');
+      let value = if path_like { "<path>" } else { value };
+      words.push(shell_quote(&format!("{}{}", key, value)));
+    }
+    words.join(" ")
+  }
+
+  pub fn return_help(mut self, return_help: bool) -> Self {
+    self.return_help = return_help;
+    self
+  }
+
+  pub fn slash_options(mut self, slash_options: bool) -> Self {
+    self.slash_options = slash_options;
+    self
+  }
+
+  pub fn value_separator(mut self, separator: char) -> Self {
+    self.value_separators.push(separator);
+    self
+  }
+
+  pub fn source_of(&self, name: &str) -> Option<ValueSource> {
+    self
+      .options
+      .iter()
+      .find(|opt| opt.name == name)
+      .and_then(|opt| opt.source)
+  }
+
+  pub fn sort_help(mut self, sort: Sort) -> Self {
+    self.sort_help = sort;
+    self
+  }
+
+  pub fn strict(mut self, strict: bool) -> Self {
+    self.strict = strict;
+    self
+  }
+
+  pub fn value_of(&self, name: &str) -> Option<&str> {
+    self.values_of(name).next()
+  }
+
+  pub fn values_of(&self, name: &str) -> impl Iterator<Item = &str> {
+    self
+      .options
+      .iter()
+      .find(|opt| opt.name == name)
+      .into_iter()
+      .flat_map(|opt| opt.values.iter().map(|value| value.as_str()))
+  }
+
+  pub fn tokenizer(mut self, tokenizer: Tokenizer) -> Self {
+    self.tokenizers.push(tokenizer);
+    self
+  }
+
+  pub fn version(mut self, version: &str) -> Self {
+    self.version = Some(version.to_owned());
+    self
+  }
+
+  fn print_version(&self) -> Result<()> {
+    let version = format!(
+      "{} {}\n",
+      self.full_name(),
+      self.version.as_deref().unwrap_or("")
+    );
+    if self.return_help {
+      return Err(OptzError::DisplayVersion(version));
+    }
+    print!("{}", version);
+    std::process::exit(0);
+  }
+
+  pub fn subcommand(mut self, subcommand: Optz) -> Self {
+    self.subcommands.push(subcommand);
+    self
+  }
+
+  pub fn subcommand_matches(&self) -> Option<(&str, &Optz)> {
+    self
+      .subcommand_matches
+      .as_deref()
+      .map(|sub| (sub.name.as_str(), sub))
+  }
+
+  pub fn route(&self) -> Route {
+    let mut route = Route {
+      path: Vec::new(),
+      handler_present: self.handler.is_some(),
+    };
+    let mut level = self;
+    while let Some(sub) = level.subcommand_matches.as_deref() {
+      route.path.push(sub.name.clone());
+      route.handler_present |= sub.handler.is_some();
+      level = sub;
+    }
+    route
+  }
+
+  pub fn full_name(&self) -> String {
+    let mut path = self.command_path.clone();
+    path.push(self.name.clone());
+    path.join(" ")
+  }
+
+  pub fn alias(mut self, alias: &str) -> Self {
+    self.aliases.push(alias.to_string());
+    self
+  }
+
+  pub fn renamed_from(mut self, name: &str) -> Self {
+    self.renamed_from.push(name.to_string());
+    self
+  }
+
+  pub fn infer_subcommands(
+    mut self,
+    infer_subcommands: bool,
+  ) -> Self {
+    self.infer_subcommands = infer_subcommands;
+    self
+  }
+
+  pub fn subcommand_required(mut self, required: bool) -> Self {
+    self.subcommand_required = required;
+    self
+  }
+
+  pub fn default_subcommand(mut self, name: &str) -> Self {
+    self.default_subcommand = Some(name.to_string());
+    self
+  }
+
+  pub fn allow_external_subcommands(mut self, allow: bool) -> Self {
+    self.allow_external_subcommands = allow;
+    self
+  }
+
+  pub fn external_subcommand(&self) -> Option<(&str, &[String])> {
+    self
+      .external_subcommand
+      .as_ref()
+      .map(|(name, args)| (name.as_str(), args.as_slice()))
+  }
+
+  pub fn external_command(&self) -> Option<Command> {
+    let (name, args) = self.external_subcommand()?;
+    let mut command = Command::new(format!("{}-{}", self.name, name));
+    command.args(args);
+    Some(command)
+  }
+
+  fn unknown_subcommand(&self, name: &str) -> Option<OptzError> {
+    if self.subcommands.is_empty()
+      || self.allow_external_subcommands
+      || self.has_positionals()
+    {
+      return None;
+    }
+    let similar = self.similar_commands(name);
+    if !self.subcommand_required
+      && (similar.is_empty() || !self.positionals().is_empty())
+    {
+      return None;
+    }
+    Some(self.unknown_command(name))
+  }
+
+  fn similar_commands(&self, name: &str) -> Vec<&str> {
+    suggest::similar(
+      name,
+      self
+        .subcommands
+        .iter()
+        .filter(|sub| !sub.hidden)
+        .flat_map(|sub| {
+          std::iter::once(&sub.name).chain(&sub.aliases)
+        })
+        .map(String::as_str),
+    )
+  }
+
+  fn unknown_command(&self, name: &str) -> OptzError {
+    let similar = self.similar_commands(name);
+    let mut msg =
+      messages::fill(&self.messages.unknown_command, &[&name]);
+    if !similar.is_empty() {
+      let similar = similar
+        .iter()
+        .map(|s| format!("'{}'", s))
+        .collect::<Vec<_>>()
+        .join(&format!(" {} ", self.messages.or));
+      msg.push_str(&messages::fill(
+        &self.messages.did_you_mean,
+        &[&similar],
+      ));
+    }
+    OptzError::UnknownOption(msg)
+  }
+
+  pub fn hidden(mut self, hidden: bool) -> Self {
+    self.hidden = hidden;
+    self
+  }
+
+  pub fn experimental(mut self, experimental: bool) -> Self {
+    self.experimental = experimental;
+    self
+  }
+
+  pub fn config_file(mut self, path: &str) -> Self {
+    self.config_file = Some(path.to_string());
+    self
+  }
+
+  pub fn config_flag(mut self, config_flag: bool) -> Self {
+    self.config_flag = config_flag;
+    self
+  }
+
+  pub fn config_search(mut self, dirs: AppDirs) -> Self {
+    self.config_search = Some(dirs);
+    self
+  }
+
+  fn load_config(&mut self) -> Result<()> {
+    let flag = self
+      .options
+      .iter()
+      .find(|opt| self.config_flag && opt.name == "config")
+      .and_then(|opt| opt.values.first());
+    let default = self
+      .config_file
+      .as_ref()
+      .map(|path| config::expand_home(path))
+      .filter(|path| path.exists());
+    let search = || {
+      let dirs = self.config_search.as_ref()?;
+      dirs.find(&self.name)
+    };
+    let path = match flag {
+      Some(path) => config::expand_home(path),
+      None => match default.or_else(search) {
+        Some(path) => path,
+        None => return Ok(()),
+      },
+    };
+    self.config_table = Some(config::load(&path, &self.messages)?);
+    Ok(())
+  }
+
+  fn apply_config(&mut self) -> Result<()> {
+    let Some(table) = &self.config_table else {
+      return Ok(());
+    };
+    for opt in self.options.iter_mut() {
+      if !opt.values.is_empty() {
+        continue;
+      }
+      let Some(values) = table.get(&opt.name).and_then(Value::values)
+      else {
+        continue;
+      };
+      opt.values = match opt.arg {
+        Arg::Flag => values
+          .iter()
+          .map(|value| {
+            parsers::boolean(value).map(|b| b.to_string()).map_err(
+              |e| {
+                opt.invalid_value(
+                  &self.messages,
+                  value,
+                  &messages::fill(
+                    &self.messages.from_config_file,
+                    &[&e],
+                  ),
+                )
+              },
+            )
+          })
+          .collect::<Result<_>>()?,
+        _ => values,
+      };
+      opt.source = Some(ValueSource::ConfigFile);
+    }
+    Ok(())
+  }
+
+  pub fn env_prefix(mut self, prefix: &str) -> Self {
+    self.env_prefix = Some(prefix.to_string());
+    self
+  }
+
+  pub fn render_env_docs(&self) -> String {
+    let mut vars = Vec::new();
+    self.env_vars(&mut vars);
+    let width = |column: fn(&EnvVar) -> &str| {
+      vars.iter().map(|var| column(var).len()).max().unwrap_or(0)
+    };
+    let name_width = width(|var| &var.name);
+    let default_width = width(|var| &var.default);
+    let mut out = String::new();
+    for var in &vars {
+      let mut line = format!("{:name_width$}  ", var.name);
+      if default_width > 0 {
+        line.push_str(&format!("{:default_width$}  ", var.default));
+      }
+      line.push_str(&var.description);
+      out.push_str(line.trim_end());
+      out.push('\n');
+    }
+    out
+  }
+
+  pub fn render_env_docs_markdown(&self) -> String {
+    let mut vars = Vec::new();
+    self.env_vars(&mut vars);
+    let code = |text: &str| {
+      if text.is_empty() {
+        String::new()
+      } else {
+        format!("`{}`", text)
+      }
+    };
+    let mut out = String::from(
+      "| Variable | Default | Description |\n\
+       | --- | --- | --- |\n",
+    );
+    for var in &vars {
+      out.push_str(&format!(
+        "| {} | {} | {} |\n",
+        code(&var.name),
+        code(&var.default),
+        var.description.replace('|', "\\|")
+      ));
+    }
+    out
+  }
+
+  fn env_vars(&self, vars: &mut Vec<EnvVar>) {
+    let mut optz = self.clone();
+    optz.prepare();
+    for opt in optz.options.iter().filter(|opt| !opt.hidden) {
+      if let Some(env) = &opt.env
+        && !vars.iter().any(|var| &var.name == env)
+      {
+        let default = opt
+          .default_value
+          .clone()
+          .or_else(|| opt.default_value_fn.map(|f| f()))
+          .filter(|_| !opt.secret);
+        vars.push(EnvVar {
+          name: env.clone(),
+          default: default.unwrap_or_default(),
+          description: opt.description.clone().unwrap_or_default(),
+        });
+      }
+    }
+    for sub in optz.subcommands.iter().filter(|sub| !sub.hidden) {
+      let mut sub = sub.clone();
+      if sub.env_prefix.is_none() {
+        sub.env_prefix.clone_from(&optz.env_prefix);
+      }
+      sub.env_vars(vars);
+    }
+  }
+
+  pub fn propagate_version(mut self, propagate: bool) -> Self {
+    self.propagate_version = propagate;
+    self
+  }
+
+  pub fn multicall(mut self, multicall: bool) -> Self {
+    self.multicall = multicall;
+    self
+  }
+
+  fn multicall_subcommand(&self) -> Option<Optz> {
+    if !self.multicall {
+      return None;
+    }
+    let program = Path::new(self.raw_args.first()?).file_stem()?;
+    let spec = self
+      .subcommands
+      .iter()
+      .find(|sub| sub.command_names().any(|name| program == name))?;
+    let name = program.to_string_lossy();
+    let mut sub =
+      self.enter_subcommand(spec, &name, 0, self.args.clone());
+    sub.command_path.clear();
+    Some(sub)
+  }
+
+  fn swallowed_subcommand(&self) -> Option<(&str, &str)> {
+    self
+      .options
+      .iter()
+      .filter(|opt| opt.source == Some(ValueSource::CommandLine))
+      .find_map(|opt| {
+        let value = opt.values.iter().find(|value| {
+          self
+            .subcommands
+            .iter()
+            .any(|sub| sub.command_names().any(|name| name == *value))
+        })?;
+        Some((opt.long.as_str(), value.as_str()))
+      })
+  }
+
+  fn command_names(&self) -> impl Iterator<Item = &str> {
+    std::iter::once(&self.name)
+      .chain(self.aliases.iter())
+      .chain(self.renamed_from.iter())
+      .map(String::as_str)
+  }
+
+  pub(crate) fn help_subcommand(&self) -> bool {
+    !self.subcommands.is_empty()
+      && !self.disable_help_flag
+      && !self.subcommands.iter().any(|sub| sub.name == "help")
+  }
+
+  fn has_positionals(&self) -> bool {
+    self
+      .rest
+      .iter()
+      .any(|arg| matches!(arg, RestArg::Positional(_)))
+  }
+
+  fn find_subcommand(&self, name: &str) -> Result<Option<&Optz>> {
+    if self.has_positionals() {
+      return Ok(None);
+    }
+    if let Some(sub) = self
+      .subcommands
+      .iter()
+      .find(|sub| sub.command_names().any(|n| n == name))
+    {
+      return Ok(Some(sub));
+    }
+    if !self.infer_subcommands {
+      return Ok(None);
+    }
+    let candidates = self
+      .subcommands
+      .iter()
+      .filter(|sub| sub.command_names().any(|n| n.starts_with(name)))
+      .collect::<Vec<_>>();
+    match candidates.as_slice() {
+      [] => Ok(None),
+      [sub] => Ok(Some(sub)),
+      _ => Err(OptzError::UnknownOption(messages::fill(
+        &self.messages.ambiguous_command,
+        &[
+          &name,
+          &candidates
+            .iter()
+            .map(|sub| sub.name.as_str())
+            .collect::<Vec<_>>()
+            .join(", "),
+        ],
+      ))),
+    }
+  }
+
+  pub fn usage<S: Into<String>>(mut self, text: S) -> Self {
+    self.usage = Some(text.into());
+    self
+  }
+}
+
+/// The result of parsing one command line, returned by `parse` and
+/// `parse_from`: values, rest, counts, sources, indices, matched
+/// events, forwarded and external arguments and the raw arguments,
+/// detached from the spec that parsed it so the spec can be reused.
+/// It keeps the spec's `Messages` so its getters report errors in the
+/// same words.
+#[derive(Clone, Default)]
+pub struct Matches {
+  pub name: String,
+  pub command_path: Vec<String>,
+  pub usage: Option<String>,
+  pub options: Vec<OptMatch>,
+  pub rest: Vec<RestArg>,
+  pub matched: Vec<Matched>,
+  pub forwarded: Vec<String>,
+  pub forward_to: Option<String>,
+  pub external_subcommand: Option<(String, Vec<String>)>,
+  pub raw_args: Vec<String>,
+  pub os_args: Vec<OsString>,
+  pub subcommand: Option<Box<Matches>>,
+  pub config: Option<Arc<dyn Any + Send + Sync>>,
+  pub messages: Messages,
+  handler_present: bool,
+  invocation: String,
+}
+
+#[derive(Clone, Default)]
+pub struct OptMatch {
+  pub name: String,
+  pub long: String,
+  pub secret: bool,
+  pub values: Vec<String>,
+  pub parsed: Vec<Arc<dyn Any + Send + Sync>>,
+  pub count: usize,
+  pub given: bool,
+  pub indices: Vec<usize>,
+  pub source: Option<ValueSource>,
+}
+
+impl From<Optz> for Matches {
+  fn from(optz: Optz) -> Self {
+    let invocation = optz.redacted_invocation();
+    let options = optz
+      .options
+      .into_iter()
+      .map(|opt| OptMatch {
+        given: opt.given(),
+        name: opt.name,
+        long: opt.long,
+        secret: opt.secret,
+        values: opt.values,
+        parsed: opt.parsed,
+        count: opt.occurrences,
+        indices: opt.indices,
+        source: opt.source,
+      })
+      .collect();
+    Matches {
+      name: optz.name,
+      command_path: optz.command_path,
+      usage: optz.usage,
+      options,
+      rest: optz.rest,
+      matched: optz.matched,
+      forwarded: optz.forwarded,
+      forward_to: optz.forward_to,
+      external_subcommand: optz.external_subcommand,
+      raw_args: optz.raw_args,
+      os_args: optz.os_args,
+      subcommand: optz
+        .subcommand_matches
+        .map(|sub| Box::new(Matches::from(*sub))),
+      config: optz.config,
+      messages: optz.messages,
+      handler_present: optz.handler.is_some(),
+      invocation,
+    }
+  }
+}
+
+impl Matches {
+  fn find(&self, name: &str) -> Result<&OptMatch> {
+    self.options.iter().find(|opt| opt.name == name).ok_or_else(
+      || {
+        OptzError::NoSuchOption(messages::fill(
+          &self.messages.no_such_option,
+          &[&name],
+        ))
+      },
+    )
+  }
+
+  pub fn external_command(&self) -> Option<Command> {
+    let (name, args) = self.external_subcommand()?;
+    let mut command = Command::new(format!("{}-{}", self.name, name));
+    command.args(args);
+    Some(command)
+  }
+
+  pub fn external_subcommand(&self) -> Option<(&str, &[String])> {
+    self
+      .external_subcommand
+      .as_ref()
+      .map(|(name, args)| (name.as_str(), args.as_slice()))
+  }
+
+  pub fn forward_command(&self) -> Option<Command> {
+    let program = self.forward_to.as_ref()?;
+    let mut command = Command::new(program);
+    command.args(&self.forwarded);
+    Some(command)
+  }
+
+  pub fn forwarded(&self) -> &[String] {
+    &self.forwarded
+  }
+
+  pub fn full_name(&self) -> String {
+    let mut path = self.command_path.clone();
+    path.push(self.name.clone());
+    path.join(" ")
+  }
+
+  pub fn get<T: FromStr>(&self, name: &str) -> Result<Option<T>>
+  where
+    <T as FromStr>::Err: std::fmt::Debug + 'static,
+  {
+    let opt = self.find(name)?;
+    parse_first(&self.messages, &opt.long, opt.secret, &opt.values)
+  }
+
+  pub fn get_bytes(&self, name: &str) -> Option<&[u8]> {
+    let opt = self.find(name).ok()?;
+    let value = opt.values.first()?;
+    let raw = (opt.source == Some(ValueSource::CommandLine))
+      .then(|| opt.indices.first())
+      .flatten()
+      .and_then(|index| {
+        raw_bytes(&self.raw_args, &self.os_args, *index, value)
+      });
+    Some(raw.unwrap_or(value.as_bytes()))
+  }
+
+  pub fn get_bytesize(&self, name: &str) -> Result<Option<u64>> {
+    self.get_with(name, parsers::bytesize)
+  }
+
+  pub fn get_config<T: 'static>(&self) -> Option<&T> {
+    self.config.as_ref().and_then(|c| c.downcast_ref::<T>())
+  }
+
+  pub fn get_duration(&self, name: &str) -> Result<Option<Duration>> {
+    self.get_with(name, parsers::duration)
+  }
+
+  pub fn get_one<T: 'static>(&self, name: &str) -> Option<&T> {
+    self
+      .find(name)
+      .ok()
+      .and_then(|opt| opt.parsed.first())
+      .and_then(|value| value.downcast_ref::<T>())
+  }
+
+  pub fn get_or<T: FromStr>(
+    &self,
+    name: &str,
+    default: T,
+  ) -> Result<T>
+  where
+    <T as FromStr>::Err: std::fmt::Debug + 'static,
+  {
+    Ok(self.get(name)?.unwrap_or(default))
+  }
+
+  pub fn get_path(&self, name: &str) -> Result<Option<PathBuf>> {
+    self.get_with(name, parsers::path)
+  }
+
+  pub fn get_positional<T: FromStr>(
+    &self,
+    name: &str,
+  ) -> Result<Option<T>>
+  where
+    <T as FromStr>::Err: std::fmt::Debug + 'static,
+  {
+    self.get(name)
+  }
+
+  pub fn get_required<T: FromStr>(&self, name: &str) -> Result<T>
+  where
+    <T as FromStr>::Err: std::fmt::Debug + 'static,
+  {
+    let long = &self.find(name)?.long;
+    self.get(name)?.ok_or_else(|| {
+      OptzError::MissingRequired(messages::fill(
+        &self.messages.required,
+        &[long],
+      ))
+    })
+  }
+
+  pub fn get_rest<T: FromStr>(&self) -> Result<Vec<T>>
+  where
+    <T as FromStr>::Err: std::fmt::Debug,
+  {
+    parse_rest(&self.messages, &self.rest)
+  }
+
+  pub fn get_socket_addr(
+    &self,
+    name: &str,
+  ) -> Result<Option<SocketAddr>> {
+    self.get_with(name, parsers::socket_addr)
+  }
+
+  pub fn get_timestamp(
+    &self,
+    name: &str,
+  ) -> Result<Option<SystemTime>> {
+    self.get_with(name, parsers::timestamp)
+  }
+
+  pub fn get_values<T: FromStr>(&self, name: &str) -> Result<Vec<T>>
+  where
+    <T as FromStr>::Err: std::fmt::Debug + 'static,
+  {
+    let opt = self.find(name)?;
+    parse_all(&self.messages, &opt.long, opt.secret, &opt.values)
+  }
+
+  fn get_with<T: Clone + 'static>(
+    &self,
+    name: &str,
+    parser: fn(&str) -> std::result::Result<T, String>,
+  ) -> Result<Option<T>> {
+    let opt = self.find(name)?;
+    if let Some(value) =
+      opt.parsed.first().and_then(|v| v.downcast_ref::<T>())
+    {
+      return Ok(Some(value.clone()));
+    }
+    parse_with(&self.messages, &opt.long, opt.secret, &opt.values, parser)
+  }
+
+  pub fn has(&self, name: &str) -> Result<bool> {
+    Ok(self.get::<bool>(name)?.unwrap_or(false))
+  }
+
+  pub fn indices_of(&self, name: &str) -> Vec<usize> {
+    self
+      .find(name)
+      .map_or_else(|_| Vec::new(), |opt| opt.indices.clone())
+  }
+
+  pub fn is_present(&self, name: &str) -> bool {
+    self.find(name).is_ok_and(|opt| opt.given)
+  }
+
+  pub fn matched(&self) -> &[Matched] {
+    &self.matched
+  }
+
+  pub fn occurrences(&self, name: &str) -> usize {
+    self.find(name).map_or(0, |opt| opt.count)
+  }
+
+  pub fn raw_args(&self) -> &[String] {
+    &self.raw_args
+  }
+
+  pub fn redacted_invocation(&self) -> &str {
+    &self.invocation
+  }
+
+  pub fn route(&self) -> Route {
+    let mut route = Route {
+      path: Vec::new(),
+      handler_present: self.handler_present,
+    };
+    let mut level = self;
+    while let Some(sub) = level.subcommand.as_deref() {
+      route.path.push(sub.name.clone());
+      route.handler_present |= sub.handler_present;
+      level = sub;
+    }
+    route
+  }
+
+  pub fn source_of(&self, name: &str) -> Option<ValueSource> {
+    self.find(name).ok().and_then(|opt| opt.source)
+  }
+
+  pub fn subcommand(&self) -> Option<(&str, &Matches)> {
+    self
+      .subcommand
+      .as_deref()
+      .map(|sub| (sub.name.as_str(), sub))
+  }
+
+  pub fn value_of(&self, name: &str) -> Option<&str> {
+    self.values_of(name).next()
+  }
+
+  pub fn values_of(&self, name: &str) -> impl Iterator<Item = &str> {
+    self
+      .find(name)
+      .into_iter()
+      .flat_map(|opt| opt.values.iter().map(|value| value.as_str()))
+  }
+}
+
+impl fmt::Debug for Matches {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let matched = redact_matched(
+      &self.matched,
+      |name| self.options.iter().any(|o| o.name == name && o.secret),
+      |index| {
+        self
+          .options
+          .iter()
+          .any(|o| o.secret && o.indices.contains(&index))
+      },
+    );
+    f.debug_struct("Matches")
+      .field("name", &self.name)
+      .field("command_path", &self.command_path)
+      .field("usage", &self.usage)
+      .field("options", &self.options)
+      .field("rest", &self.rest)
+      .field("matched", &matched)
+      .field("forwarded", &self.forwarded)
+      .field("forward_to", &self.forward_to)
+      .field("external_subcommand", &self.external_subcommand)
+      .field("raw_args", &self.raw_args.len())
+      .field("os_args", &self.os_args.len())
+      .field("subcommand", &self.subcommand)
+      .field("config", &self.config.as_ref().map(|_| "config"))
+      .field("messages", &self.messages)
+      .field("handler_present", &self.handler_present)
+      .field("invocation", &self.invocation)
+      .finish()
+  }
+}
+
+impl fmt::Debug for OptMatch {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let values = self
+      .values
+      .iter()
+      .map(|value| if self.secret { REDACTED } else { value })
+      .collect::<Vec<_>>();
+    f.debug_struct("OptMatch")
+      .field("name", &self.name)
+      .field("long", &self.long)
+      .field("secret", &self.secret)
+      .field("values", &values)
+      .field("parsed", &self.parsed.len())
+      .field("count", &self.count)
+      .field("given", &self.given)
+      .field("indices", &self.indices)
+      .field("source", &self.source)
+      .finish()
+  }
+}
+
+impl fmt::Display for Matches {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let rows = self
+      .options
+      .iter()
+      .filter(|opt| !opt.values.is_empty())
+      .map(|opt| {
+        let values = if opt.secret {
+          REDACTED.to_string()
+        } else {
+          opt.values.join(", ")
+        };
+        let source =
+          opt.source.map_or(String::new(), |s| s.to_string());
+        (opt.long.as_str(), values, source)
+      })
+      .collect::<Vec<_>>();
+    write_summary(f, &rows, &self.rest)
+  }
+}
+
+impl fmt::Display for Optz {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let rows = self
+      .options
+      .iter()
+      .filter(|opt| !opt.values.is_empty())
+      .map(|opt| {
+        let values = opt
+          .values
+          .iter()
+          .map(|v| opt.display_value(v))
+          .collect::<Vec<_>>()
+          .join(", ");
+        let source =
+          opt.source.map_or(String::new(), |s| s.to_string());
+        (opt.long.as_str(), values, source)
+      })
+      .collect::<Vec<_>>();
+    write_summary(f, &rows, &self.rest)
+  }
+}
+
+fn write_summary(
+  f: &mut fmt::Formatter<'_>,
+  rows: &[(&str, String, String)],
+  rest: &[RestArg],
+) -> fmt::Result {
+  let long_width = rows.iter().map(|r| r.0.len()).max().unwrap_or(0);
+  let value_width = rows.iter().map(|r| r.1.len()).max().unwrap_or(0);
+  for (long, values, source) in rows {
+    let line = format!(
+      "{:<lw$}  {:<vw$}  {}",
+      long,
+      values,
+      source,
+      lw = long_width,
+      vw = value_width
+    );
+    writeln!(f, "{}", line.trim_end())?;
+  }
+  if !rest.is_empty() {
+    let rest = rest
+      .iter()
+      .map(|arg| arg.as_str())
+      .collect::<Vec<_>>()
+      .join(" ");
+    writeln!(f, "rest: {}", rest)?;
+  }
+  Ok(())
+}
+
+impl fmt::Debug for Optz {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let matched = redact_matched(
+      &self.matched,
+      |name| self.options.iter().any(|o| o.name == name && o.secret),
+      |index| {
+        self
+          .options
+          .iter()
+          .any(|o| o.secret && o.indices.contains(&index))
+      },
+    );
+    let raw_args = self
+      .raw_args
+      .first()
+      .into_iter()
+      .cloned()
+      .chain(self.redacted_args())
+      .collect::<Vec<_>>();
+    f.debug_struct("Optz")
+      .field("args", &self.redacted_args())
+      .field("handler", &self.handler.map(|_| "handler"))
+      .field("name", &self.name)
+      .field("version", &self.version)
+      .field("usage", &self.usage)
+      .field("description", &self.description)
+      .field("long_about", &self.long_about)
+      .field("authors", &self.authors)
+      .field("options", &self.options)
+      .field("groups", &self.groups)
+      .field("config", &self.config.as_ref().map(|_| "config"))
+      .field("lenient", &self.lenient)
+      .field("rest", &self.rest)
+      .field("strict", &self.strict)
+      .field("slash_options", &self.slash_options)
+      .field("value_separators", &self.value_separators)
+      .field("expand_env", &self.expand_env)
+      .field("forward_to", &self.forward_to)
+      .field("forwarded", &self.forwarded)
+      .field("matched", &matched)
+      .field(
+        "tokenizers",
+        &self.tokenizers.iter().map(|_| "fn").collect::<Vec<_>>(),
+      )
+      .field("raw_args", &raw_args)
+      .field("os_args", &self.os_args.len())
+      .field("help_translator", &self.help_translator.map(|_| "fn"))
+      .field("hide_description", &self.hide_description)
+      .field("hide_authors", &self.hide_authors)
+      .field("hide_annotations", &self.hide_annotations)
+      .field("color", &self.color)
+      .field("help_template", &self.help_template)
+      .field("before_help", &self.before_help)
+      .field("after_help", &self.after_help)
+      .field("examples", &self.examples)
+      .field("sort_help", &self.sort_help)
+      .field("paged_help", &self.paged_help)
+      .field("disable_help_flag", &self.disable_help_flag)
+      .field("help_flag_names", &self.help_flag_names)
+      .field("arg_required_else_help", &self.arg_required_else_help)
+      .field("return_help", &self.return_help)
+      .field("messages", &self.messages)
+      .field("command_path", &self.command_path)
+      .field("subcommands", &self.subcommands)
+      .field("subcommand_matches", &self.subcommand_matches)
+      .field("arg_offset", &self.arg_offset)
+      .field("aliases", &self.aliases)
+      .field("renamed_from", &self.renamed_from)
+      .field("infer_subcommands", &self.infer_subcommands)
+      .field("subcommand_required", &self.subcommand_required)
+      .field("default_subcommand", &self.default_subcommand)
+      .field(
+        "allow_external_subcommands",
+        &self.allow_external_subcommands,
+      )
+      .field("external_subcommand", &self.external_subcommand)
+      .field("multicall", &self.multicall)
+      .field("propagate_version", &self.propagate_version)
+      .field("hidden", &self.hidden)
+      .field("experimental", &self.experimental)
+      .field("env_prefix", &self.env_prefix)
+      .field("config_file", &self.config_file)
+      .field("config_flag", &self.config_flag)
+      .field(
+        "config_table",
+        &self.config_table.as_ref().map(Value::keys),
+      )
+      .field("config_search", &self.config_search)
+      .finish()
+  }
+}
+
+fn redact_matched(
+  matched: &[Matched],
+  secret_name: impl Fn(&str) -> bool,
+  secret_index: impl Fn(usize) -> bool,
+) -> Vec<Matched> {
+  matched
+    .iter()
+    .map(|m| match m {
+      Matched::Option { name, index, .. } if secret_name(name) => {
+        Matched::Option {
+          name: name.clone(),
+          value: REDACTED.to_string(),
+          index: *index,
+        }
+      }
+      Matched::Positional { index, .. } if secret_index(*index) => {
+        Matched::Positional {
+          value: REDACTED.to_string(),
+          index: *index,
+        }
+      }
+      m => m.clone(),
+    })
+    .collect()
+}
+
+fn parse_with<T: Clone + 'static>(
+  messages: &Messages,
+  long: &str,
+  secret: bool,
+  values: &[String],
+  parser: fn(&str) -> std::result::Result<T, String>,
+) -> Result<Option<T>> {
+  values
+    .first()
+    .map(|value| {
+      parser(value).map_err(|e| {
+        invalid_value(messages, long, secret, value, &e)
+      })
+    })
+    .transpose()
+}
+
+fn parse_rest<T: FromStr>(
+  messages: &Messages,
+  rest: &[RestArg],
+) -> Result<Vec<T>>
+where
+  <T as FromStr>::Err: std::fmt::Debug,
+{
+  rest
+    .iter()
+    .filter_map(|arg| match arg {
+      RestArg::Positional(value) => Some(value),
+      RestArg::UnknownOption(_) => None,
+    })
+    .map(|value| {
+      value.parse::<T>().map_err(|e| {
+        OptzError::Parse(messages::fill(
+          &messages.invalid_positional,
+          &[value, &format!("{:?}", e)],
+        ))
+      })
+    })
+    .collect()
+}
+
+// The raw OS bytes of `value`, which ends the token at `index`, e.g.
+// `--key=<value>`. Only slices when the prefix is the same bytes in
+// both forms; a lossy prefix is longer than the raw bytes it replaced.
+fn raw_bytes<'a>(
+  raw_args: &[String],
+  os_args: &'a [OsString],
+  index: usize,
+  value: &str,
+) -> Option<&'a [u8]> {
+  let token = raw_args.get(index)?;
+  let arg = os_args.get(index)?;
+  let prefix = token.strip_suffix(value)?;
+  let bytes = arg.as_encoded_bytes();
+  (arg.to_string_lossy() == *token
+    && bytes.get(..prefix.len()) == Some(prefix.as_bytes()))
+  .then(|| &bytes[prefix.len()..])
+}
+
+fn invalid_value(
+  messages: &Messages,
+  long: &str,
+  secret: bool,
+  value: &str,
+  msg: &str,
+) -> OptzError {
+  let shown = if secret { REDACTED } else { value };
+  // Custom parsers and validators may quote the value they reject.
+  let msg = if secret && !value.is_empty() {
+    msg.replace(value, REDACTED)
+  } else {
+    msg.to_string()
+  };
+  OptzError::InvalidValue(messages::fill(
+    &messages.invalid_value,
+    &[&shown, &long, &msg],
+  ))
+}
+
+fn parse_first<T: FromStr>(
+  messages: &Messages,
+  long: &str,
+  secret: bool,
+  values: &[String],
+) -> Result<Option<T>>
+where
+  <T as FromStr>::Err: std::fmt::Debug + 'static,
+{
+  values
+    .first()
+    .map(|value| {
+      value.parse::<T>().map_err(|e| {
+        if let Some(err) =
+          numeric_error::<T>(messages, long, secret, value, &e)
+        {
+          return err;
+        }
+        OptzError::Parse(messages::fill(
+          &messages.invalid_value,
+          &[
+            &if secret { REDACTED } else { value },
+            &long,
+            &format!("{:?}", e),
+          ],
+        ))
+      })
+    })
+    .transpose()
+}
+
+fn parse_all<T: FromStr>(
+  messages: &Messages,
+  long: &str,
+  secret: bool,
+  values: &[String],
+) -> Result<Vec<T>>
+where
+  <T as FromStr>::Err: std::fmt::Debug + 'static,
+{
+  values
+    .iter()
+    .enumerate()
+    .map(|(i, s)| {
+      s.parse::<T>().map_err(|e| {
+        if let Some(err) =
+          numeric_error::<T>(messages, long, secret, s, &e)
+        {
+          return err;
+        }
+        OptzError::Parse(messages::fill(
+          &messages.invalid_value_at,
+          &[
+            &if secret { REDACTED } else { s },
+            &(i + 1),
+            &long,
+            &format!("{:?}", e),
+          ],
+        ))
+      })
+    })
+    .collect()
+}
+
+fn numeric_error<T: FromStr>(
+  messages: &Messages,
+  long: &str,
+  secret: bool,
+  value: &str,
+  e: &T::Err,
+) -> Option<OptzError>
+where
+  <T as FromStr>::Err: 'static,
+{
+  let e = (e as &dyn Any).downcast_ref::<ParseIntError>()?;
+  let target = std::any::type_name::<T>();
+  let shown = if secret { REDACTED } else { value };
+  match e.kind() {
+    IntErrorKind::PosOverflow | IntErrorKind::NegOverflow => {}
+    // A negative number for an unsigned type.
+    IntErrorKind::InvalidDigit if value.parse::<i128>().is_ok() => {}
+    IntErrorKind::InvalidDigit
+      if value.parse::<f64>().is_ok_and(|f| f.is_finite()) =>
+    {
+      return Some(OptzError::LossyConversion(messages::fill(
+        &messages.not_whole,
+        &[&shown, &long, &target],
+      )));
+    }
+    _ => return None,
+  }
+  Some(OptzError::NumericOverflow(messages::fill(
+    &messages.out_of_range,
+    &[&shown, &long, &target],
+  )))
+}
+
+// A renamed option or command given several times is reported once.
+fn warn_once(warnings: &mut Vec<String>, warning: String) {
+  if !warnings.contains(&warning) {
+    eprintln!("{}", warning);
+    warnings.push(warning);
+  }
+}
+
+fn terminal_height() -> usize {
+  if let Some(lines) =
+    env::var("LINES").ok().and_then(|l| l.parse().ok())
+  {
+    return lines;
+  }
+  Command::new("stty")
+    .arg("size")
+    .stdin(Stdio::inherit())
+    .output()
+    .ok()
+    .and_then(|out| {
+      let size = String::from_utf8(out.stdout).ok()?;
+      size.split_whitespace().next()?.parse().ok()
+    })
+    .unwrap_or(24)
+}
+
+fn page(text: &str) -> io::Result<()> {
+  let pager = env::var("PAGER")
+    .ok()
+    .filter(|pager| !pager.trim().is_empty())
+    .unwrap_or_else(|| "less -R".to_string());
+  let mut words = pager.split_whitespace();
+  let program = words.next().unwrap_or("less");
+  let mut child = Command::new(program)
+    .args(words)
+    .stdin(Stdio::piped())
+    .spawn()?;
+  if let Some(mut stdin) = child.stdin.take() {
+    let _ = stdin.write_all(text.as_bytes());
+  }
+  child.wait()?;
+  Ok(())
+}
+
+fn shell_quote(word: &str) -> String {
+  let safe = !word.is_empty()
+    && word
+      .chars()
+      .all(|c| c.is_ascii_alphanumeric() || "-_=.,:/@+%".contains(c));
+  if safe {
+    word.to_string()
+  } else {
+    format!("'{}'", word.replace('\'', "'\\''"))
+  }
+}
+
+fn unknown_option(messages: &Messages, arg: &str) -> OptzError {
+  let mut msg =
+    messages::fill(&messages.unknown_option, &[&arg, &arg]);
+  if Path::new(arg).exists() {
+    msg.push_str(&messages::fill(
+      &messages.unknown_option_path,
+      &[&arg],
+    ));
+  }
+  OptzError::UnknownOption(msg)
+}
+
+impl IntoIterator for Optz {
+  type Item = Opt;
+  type IntoIter = std::vec::IntoIter<Self::Item>;
+
+  fn into_iter(self) -> Self::IntoIter {
+    self.options.into_iter()
+  }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ValueSource {
+  CommandLine,
+  Environment,
+  ConfigFile,
+  Default,
+}
+
+impl fmt::Display for ValueSource {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let source = match self {
+      ValueSource::CommandLine => "command line",
+      ValueSource::Environment => "environment",
+      ValueSource::ConfigFile => "config file",
+      ValueSource::Default => "default",
+    };
+    write!(f, "{}", source)
+  }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Matched {
+  Option {
+    name: String,
+    value: String,
+    index: usize,
+  },
+  Positional {
+    value: String,
+    index: usize,
+  },
+}
+
+/// The subcommands matched below the root, outermost first, and
+/// whether `dispatch()` would find a handler to run for them.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Route {
+  pub path: Vec<String>,
+  pub handler_present: bool,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum RestArg {
+  Positional(String),
+  UnknownOption(String),
+}
+
+impl RestArg {
+  pub fn as_str(&self) -> &str {
+    match self {
+      RestArg::Positional(arg) | RestArg::UnknownOption(arg) => arg,
+    }
+  }
+}
+
+impl fmt::Display for RestArg {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{}", self.as_str())
+  }
+}
+
+impl PartialEq<str> for RestArg {
+  fn eq(&self, other: &str) -> bool {
+    self.as_str() == other
+  }
+}
+
+impl PartialEq<&str> for RestArg {
+  fn eq(&self, other: &&str) -> bool {
+    self.as_str() == *other
+  }
+}
+
+#[allow(clippy::enum_variant_names)]
+#[derive(Clone, Debug, Default)]
+pub enum Arg {
+  Arg,
+  #[default]
+  Flag,
+  Positional,
+}
+
+#[derive(Clone, Default)]
+pub struct Opt {
+  pub aliases: Vec<(String, bool)>,
+  pub arg: Arg,
+  pub conflicts_with: Vec<String>,
+  pub default_from: Option<DefaultFrom>,
+  pub default_value: Option<String>,
+  pub default_value_fn: Option<fn() -> String>,
+  pub default_value_ifs: Vec<(String, Option<String>, String)>,
+  pub deprecated: Option<String>,
+  pub description: Option<String>,
+  pub display_order: Option<usize>,
+  pub env: Option<String>,
+  pub skip_env: bool,
+  pub exclusive: bool,
+  pub exec_safe: bool,
+  pub global: bool,
+  pub handler: Option<fn(&Optz) -> Result<()>>,
+  pub help_heading: Option<String>,
+  pub hidden: bool,
+  pub long: String,
+  pub long_description: Option<String>,
+  pub multiple: bool,
+  pub index: Option<usize>,
+  pub indices: Vec<usize>,
+  pub literal: bool,
+  pub many: Option<(usize, Option<usize>)>,
+  pub name: String,
+  pub occurrences: usize,
+  pub possible_values: Vec<String>,
+  pub prefix_match: bool,
+  pub parsed: Vec<Arc<dyn Any + Send + Sync>>,
+  pub required_if_eq: Vec<(String, String)>,
+  pub renamed_from: Vec<String>,
+  pub require_equals: bool,
+  pub required: bool,
+  pub requires: Vec<String>,
+  pub range: Option<(Bound<i64>, Bound<i64>)>,
+  pub secret: bool,
+  pub short: Option<String>,
+  pub source: Option<ValueSource>,
+  pub validators: Vec<Validator>,
+  pub value_name: Option<String>,
+  pub value_parser: Option<ValueParser>,
+  pub values: Vec<String>,
+}
+
+impl Opt {
+  pub fn flag(name: &str) -> Self {
+    let long = format!("--{}", name);
+    Self {
+      arg: Arg::Flag,
+      name: name.to_owned(),
+      long,
+      ..Default::default()
+    }
+  }
+
+  pub fn arg(name: &str) -> Self {
+    let long = format!("--{}", name);
+    Self {
+      arg: Arg::Arg,
+      name: name.to_owned(),
+      long,
+      ..Default::default()
+    }
+  }
+
+  pub fn positional(name: &str) -> Self {
+    Self {
+      arg: Arg::Positional,
+      name: name.to_owned(),
+      long: format!("<{}>", name),
+      ..Default::default()
+    }
+  }
+
+  pub fn index(mut self, index: usize) -> Self {
+    self.index = Some(index);
+    self
+  }
+
+  pub fn renamed_from(mut self, name: &str) -> Self {
+    self.renamed_from.push(format!("--{}", name));
+    self
+  }
+
+  pub fn require_equals(mut self, require_equals: bool) -> Self {
+    self.require_equals = require_equals;
+    self
+  }
+
+  pub fn required(mut self, required: bool) -> Self {
+    self.required = required;
+    self
+  }
+
+  pub fn path(name: &str) -> Self {
+    Self::arg(name).value_parser(parsers::path)
+  }
+
+  pub fn must_exist(self) -> Self {
+    self.value_parser(parsers::existing_path)
+  }
+
+  pub fn bytes(self) -> Self {
+    self.value_parser(parsers::bytesize)
+  }
+
+  pub fn duration(name: &str) -> Self {
+    Self::arg(name).value_parser(parsers::duration)
+  }
+
+  pub fn ip_addr(name: &str) -> Self {
+    Self::arg(name).value_parser(parsers::ip_addr)
+  }
+
+  pub fn socket_addr(name: &str) -> Self {
+    Self::arg(name).value_parser(parsers::socket_addr)
+  }
+
+  pub fn timestamp(name: &str) -> Self {
+    Self::arg(name).value_parser(parsers::timestamp)
+  }
+
+  pub fn url(name: &str) -> Self {
+    Self::arg(name).value_parser(parsers::url)
+  }
+
+  pub fn literal(mut self, literal: bool) -> Self {
+    self.literal = literal;
+    self
+  }
+
+  pub fn long_description(mut self, desc: &str) -> Self {
+    self.long_description = Some(desc.into());
+    self
+  }
+
+  pub fn many<R: RangeBounds<usize>>(mut self, count: R) -> Self {
+    let min = match count.start_bound() {
+      Bound::Included(n) => *n,
+      Bound::Excluded(n) => n + 1,
+      Bound::Unbounded => 0,
+    };
+    let max = match count.end_bound() {
+      Bound::Included(n) => Some(*n),
+      Bound::Excluded(n) => Some(n.saturating_sub(1)),
+      Bound::Unbounded => None,
+    };
+    self.many = Some((min, max));
+    self.multiple = true;
+    self
+  }
+
+  pub fn multiple(mut self, multiple: bool) -> Self {
+    self.multiple = multiple;
+    self
+  }
+
+  pub fn alias(mut self, alias: &str) -> Self {
+    self.aliases.push((alias.to_owned(), false));
+    self
+  }
+
+  pub fn visible_alias(mut self, alias: &str) -> Self {
+    self.aliases.push((alias.to_owned(), true));
+    self
+  }
+
+  pub fn short_alias(self, alias: &str) -> Self {
+    self.alias(alias)
+  }
+
+  pub fn visible_short_alias(self, alias: &str) -> Self {
+    self.visible_alias(alias)
+  }
+
+  pub fn conflicts_with(mut self, name: &str) -> Self {
+    self.conflicts_with.push(name.to_owned());
+    self
+  }
+
+  pub fn default_value(mut self, value: &str) -> Self {
+    self.default_value = Some(value.to_owned());
+    self.default_value_fn = None;
+    self.default_from = None;
+    self
+  }
+
+  pub fn default_from<C: 'static>(
+    mut self,
+    f: fn(&C) -> String,
+  ) -> Self {
+    self.default_from =
+      Some(Arc::new(move |config| config.downcast_ref::<C>().map(f)));
+    self.default_value = None;
+    self.default_value_fn = None;
+    self
+  }
+
+  pub fn default_value_fn(mut self, f: fn() -> String) -> Self {
+    self.default_value_fn = Some(f);
+    self.default_value = None;
+    self.default_from = None;
+    self
+  }
+
+  pub fn default_value_if(
+    mut self,
+    other: &str,
+    value: Option<&str>,
+    default: &str,
+  ) -> Self {
+    self.default_value_ifs.push((
+      other.to_owned(),
+      value.map(|v| v.to_owned()),
+      default.to_owned(),
+    ));
+    self
+  }
+
+  pub fn deprecated(mut self, note: &str) -> Self {
+    self.deprecated = Some(note.to_owned());
+    self
+  }
+
+  pub fn description(mut self, desc: &str) -> Self {
+    self.description = Some(desc.into());
+    self
+  }
+
+  pub fn display_order(mut self, order: usize) -> Self {
+    self.display_order = Some(order);
+    self
+  }
+
+  pub fn env(mut self, name: &str) -> Self {
+    self.env = Some(name.to_string());
+    self
+  }
+
+  pub fn skip_env(mut self, skip_env: bool) -> Self {
+    self.skip_env = skip_env;
+    self
+  }
+
+  fn apply_env(&mut self, messages: &Messages) -> Result<()> {
+    if !self.values.is_empty() {
+      return Ok(());
+    }
+    let Some(name) = &self.env else {
+      return Ok(());
+    };
+    let Some(value) = env::var(name).ok().filter(|v| !v.is_empty())
+    else {
+      return Ok(());
+    };
+    let value = match self.arg {
+      Arg::Flag => parsers::boolean(&value)
+        .map_err(|e| {
+          self.invalid_value(
+            messages,
+            &value,
+            &messages::fill(&messages.from_env, &[&e, name]),
+          )
+        })?
+        .to_string(),
+      _ => value,
+    };
+    self.values = vec![value];
+    self.source = Some(ValueSource::Environment);
+    Ok(())
+  }
+
+  pub fn exclusive(mut self, exclusive: bool) -> Self {
+    self.exclusive = exclusive;
+    self
+  }
+
+  pub fn exec_safe(mut self, exec_safe: bool) -> Self {
+    self.exec_safe = exec_safe;
+    self
+  }
+
+  pub fn global(mut self, global: bool) -> Self {
+    self.global = global;
+    self
+  }
+
+  fn copy_values(&mut self, from: &Opt) {
+    self.values.clone_from(&from.values);
+    self.parsed.clone_from(&from.parsed);
+    self.occurrences = from.occurrences;
+    self.source = from.source;
+  }
+
+  pub fn handler(mut self, handler: fn(&Optz) -> Result<()>) -> Self {
+    self.handler = Some(handler);
+    self
+  }
+
+  pub fn help_heading(mut self, heading: &str) -> Self {
+    self.help_heading = Some(heading.to_owned());
+    self
+  }
+
+  pub fn hidden(mut self, hidden: bool) -> Self {
+    self.hidden = hidden;
+    self
+  }
+
+  pub fn possible_values(mut self, values: &[&str]) -> Self {
+    self.possible_values =
+      values.iter().map(|v| v.to_string()).collect();
+    self
+  }
+
+  pub fn prefix_match(mut self, prefix_match: bool) -> Self {
+    self.prefix_match = prefix_match;
+    self
+  }
+
+  pub fn range<R: RangeBounds<i64>>(mut self, range: R) -> Self {
+    self.range = Some((
+      range.start_bound().cloned(),
+      range.end_bound().cloned(),
+    ));
+    self
+  }
+
+  pub fn required_if_eq(mut self, other: &str, value: &str) -> Self {
+    self
+      .required_if_eq
+      .push((other.to_owned(), value.to_owned()));
+    self
+  }
+
+  pub fn requires(mut self, name: &str) -> Self {
+    self.requires.push(name.to_owned());
+    self
+  }
+
+  pub fn secret(mut self, secret: bool) -> Self {
+    self.secret = secret;
+    self
+  }
+
+  pub fn short(mut self, short: &str) -> Self {
+    self.short = Some(short.into());
+    self
+  }
+
+  pub fn validator(mut self, validator: Validator) -> Self {
+    self.validators.push(validator);
+    self
+  }
+
+  pub fn value_name(mut self, value_name: &str) -> Self {
+    self.value_name = Some(value_name.to_owned());
+    self
+  }
+
+  pub fn value_parser<T, F>(mut self, parser: F) -> Self
+  where
+    T: Send + Sync + 'static,
+    F: Fn(&str) -> std::result::Result<T, String>
+      + Send
+      + Sync
+      + 'static,
+  {
+    self.value_parser = Some(Arc::new(move |s| {
+      parser(s).map(|v| Arc::new(v) as Arc<dyn Any + Send + Sync>)
+    }));
+    self
+  }
+
+  fn matches(&self, key: &str) -> bool {
+    if let Arg::Positional = self.arg {
+      return false;
+    }
+    self.long == key
+      || self.short.as_deref() == Some(key)
+      || self.aliases.iter().any(|(alias, _)| alias == key)
+      || self.renamed_from.iter().any(|old| old == key)
+  }
+
+  // Has a value; a flag only counts when that value is true, so
+  // `--quiet=false` leaves it unset.
+  fn is_set(&self) -> bool {
+    match self.arg {
+      Arg::Flag => self.values.iter().any(|value| value == "true"),
+      _ => !self.values.is_empty(),
+    }
+  }
+
+  fn given(&self) -> bool {
+    self.occurrences > 0 && self.is_set()
+  }
+
+  fn push_value(&mut self, value: String, index: usize) {
+    self.source = Some(ValueSource::CommandLine);
+    if self.multiple {
+      self.values.push(value);
+      self.indices.push(index);
+    } else {
+      self.values = vec![value];
+      self.indices = vec![index];
+    }
+  }
+
+  pub(crate) fn placeholder(&self) -> String {
+    format!("<{}>", self.value_name.as_deref().unwrap_or(&self.name))
+  }
+
+  fn display_value<'a>(&self, value: &'a str) -> &'a str {
+    if self.secret { REDACTED } else { value }
+  }
+
+  fn invalid_value(
+    &self,
+    messages: &Messages,
+    value: &str,
+    msg: &str,
+  ) -> OptzError {
+    invalid_value(messages, &self.long, self.secret, value, msg)
+  }
+
+  fn parse_values(&mut self, messages: &Messages) -> Result<()> {
+    if let Some(parser) = &self.value_parser {
+      self.parsed = self
+        .values
+        .iter()
+        .map(|value| {
+          parser(value)
+            .map_err(|e| self.invalid_value(messages, value, &e))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    }
+    Ok(())
+  }
+
+  fn resolve_possible_values(
+    &mut self,
+    messages: &Messages,
+  ) -> Result<()> {
+    if self.possible_values.is_empty() {
+      return Ok(());
+    }
+    let mut resolved = Vec::with_capacity(self.values.len());
+    for value in &self.values {
+      if self.possible_values.contains(value) {
+        resolved.push(value.clone());
+        continue;
+      }
+      let candidates = self
+        .possible_values
+        .iter()
+        .filter(|p| {
+          self.prefix_match && p.starts_with(value.as_str())
+        })
+        .map(|p| p.as_str())
+        .collect::<Vec<_>>();
+      match candidates.as_slice() {
+        [candidate] => resolved.push(candidate.to_string()),
+        [] => {
+          return Err(self.invalid_value(
+            messages,
+            value,
+            &messages::fill(
+              &messages.expected_one_of,
+              &[&self.possible_values.join(", ")],
+            ),
+          ));
+        }
+        _ => {
+          return Err(self.invalid_value(
+            messages,
+            value,
+            &messages::fill(
+              &messages.ambiguous_value,
+              &[&candidates.join(", ")],
+            ),
+          ));
+        }
+      }
+    }
+    self.values = resolved;
+    Ok(())
+  }
+
+  fn validate(&self, messages: &Messages) -> Result<()> {
+    for validator in &self.validators {
+      for value in &self.values {
+        validator(value)
+          .map_err(|e| self.invalid_value(messages, value, &e))?;
+      }
+    }
+    if self.exec_safe {
+      for value in &self.values {
+        if value.contains(['\0', '\n', '\r']) {
+          return Err(OptzError::InvalidValue(messages::fill(
+            &messages.unsafe_value,
+            &[&self.long],
+          )));
+        }
+      }
+    }
+    if let Some(range) = &self.range {
+      for value in &self.values {
+        let num = value.parse::<i64>().map_err(|_| {
+          OptzError::InvalidValue(messages::fill(
+            &messages.not_a_number,
+            &[&self.display_value(value), &self.long],
+          ))
+        })?;
+        if !range.contains(&num) {
+          return Err(OptzError::InvalidValue(messages::fill(
+            &messages.not_in_range,
+            &[
+              &self.display_value(value),
+              &self.long,
+              &format_range(range),
+            ],
+          )));
+        }
+      }
+    }
+    Ok(())
+  }
+}
+
+fn format_range(range: &(Bound<i64>, Bound<i64>)) -> String {
+  let start = match range.0 {
+    Bound::Included(n) | Bound::Excluded(n) => n.to_string(),
+    Bound::Unbounded => String::new(),
+  };
+  let end = match range.1 {
+    Bound::Included(n) => format!("..={}", n),
+    Bound::Excluded(n) => format!("..{}", n),
+    Bound::Unbounded => "..".to_string(),
+  };
+  format!("{}{}", start, end)
 }
 
 impl fmt::Debug for Opt {
   fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
     f.debug_struct("Opt")
+      .field("aliases", &self.aliases)
       .field("arg", &self.arg)
+      .field("conflicts_with", &self.conflicts_with)
+      .field(
+        "default_from",
+        &self.default_from.as_ref().map(|_| "fn"),
+      )
+      .field("default_value", &self.default_value)
+      .field("default_value_fn", &self.default_value_fn.map(|_| "fn"))
+      .field("default_value_ifs", &self.default_value_ifs)
+      .field("deprecated", &self.deprecated)
       .field("description", &self.description)
+      .field("display_order", &self.display_order)
+      .field("env", &self.env)
+      .field("skip_env", &self.skip_env)
+      .field("exclusive", &self.exclusive)
+      .field("exec_safe", &self.exec_safe)
+      .field("global", &self.global)
       .field("handler", &"handler")
+      .field("help_heading", &self.help_heading)
+      .field("hidden", &self.hidden)
+      .field("index", &self.index)
+      .field("indices", &self.indices)
+      .field("literal", &self.literal)
       .field("long", &self.long)
+      .field("long_description", &self.long_description)
+      .field("many", &self.many)
       .field("name", &self.name)
+      .field("occurrences", &self.occurrences)
+      .field("possible_values", &self.possible_values)
+      .field("prefix_match", &self.prefix_match)
+      .field("range", &self.range)
+      .field("required_if_eq", &self.required_if_eq)
+      .field("renamed_from", &self.renamed_from)
+      .field("require_equals", &self.require_equals)
+      .field("required", &self.required)
+      .field("requires", &self.requires)
+      .field("secret", &self.secret)
       .field("short", &self.short)
-      .field("values", &self.values)
+      .field("source", &self.source)
+      .field("value_name", &self.value_name)
+      .field(
+        "value_parser",
+        &self.value_parser.as_ref().map(|_| "parser"),
+      )
+      .field(
+        "values",
+        &self
+          .values
+          .iter()
+          .map(|v| self.display_value(v))
+          .collect::<Vec<_>>(),
+      )
       .finish()
   }
 }
@@ -0,0 +1,364 @@
+use std::fmt;
+use std::net::{IpAddr, Ipv6Addr, SocketAddr};
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+
+pub fn path(s: &str) -> Result<PathBuf, String> {
+  if s.is_empty() {
+    return Err("path is empty".to_string());
+  }
+  Ok(PathBuf::from(s))
+}
+
+pub fn existing_path(s: &str) -> Result<PathBuf, String> {
+  let path = path(s)?;
+  if !path.exists() {
+    return Err("path does not exist".to_string());
+  }
+  Ok(path)
+}
+
+pub fn boolean(s: &str) -> Result<bool, String> {
+  match s.to_ascii_lowercase().as_str() {
+    "true" | "yes" | "on" | "1" => Ok(true),
+    "false" | "no" | "off" | "0" => Ok(false),
+    _ => Err(
+      "expected one of true, false, yes, no, on, off, 1, 0"
+        .to_string(),
+    ),
+  }
+}
+
+pub fn bytesize(s: &str) -> Result<u64, String> {
+  let invalid = || {
+    "invalid size; accepted suffixes: B, K, KB, KiB, M, MB, MiB, G, \
+     GB, GiB, T, TB, TiB"
+      .to_string()
+  };
+  let digits =
+    s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+  if digits == 0 {
+    return Err(invalid());
+  }
+  let num = s[..digits].parse::<u64>().map_err(|_| invalid())?;
+  let multiplier: u64 =
+    match s[digits..].trim_start().to_ascii_lowercase().as_str() {
+      "" | "b" => 1,
+      "k" | "kib" => 1 << 10,
+      "kb" => 1_000,
+      "m" | "mib" => 1 << 20,
+      "mb" => 1_000_000,
+      "g" | "gib" => 1 << 30,
+      "gb" => 1_000_000_000,
+      "t" | "tib" => 1 << 40,
+      "tb" => 1_000_000_000_000,
+      _ => return Err(invalid()),
+    };
+  num
+    .checked_mul(multiplier)
+    .ok_or_else(|| "size is too large".to_string())
+}
+
+pub fn duration(s: &str) -> Result<Duration, String> {
+  let invalid = || {
+    "invalid duration; expected e.g. 30s, 5m or 1h30m (units: ms, s, \
+     m, h, d)"
+      .to_string()
+  };
+  if s.is_empty() {
+    return Err(invalid());
+  }
+  let mut total = Duration::ZERO;
+  let mut rest = s;
+  while !rest.is_empty() {
+    let digits = rest
+      .find(|c: char| !c.is_ascii_digit())
+      .ok_or_else(invalid)?;
+    if digits == 0 {
+      return Err(invalid());
+    }
+    let num = rest[..digits].parse::<u64>().map_err(|_| invalid())?;
+    rest = &rest[digits..];
+    let unit_len = rest
+      .find(|c: char| c.is_ascii_digit())
+      .unwrap_or(rest.len());
+    let millis = match &rest[..unit_len] {
+      "ms" => 1,
+      "s" => 1_000,
+      "m" => 60_000,
+      "h" => 3_600_000,
+      "d" => 86_400_000,
+      _ => return Err(invalid()),
+    };
+    let part = num
+      .checked_mul(millis)
+      .map(Duration::from_millis)
+      .ok_or_else(|| "duration is too large".to_string())?;
+    total = total
+      .checked_add(part)
+      .ok_or_else(|| "duration is too large".to_string())?;
+    rest = &rest[unit_len..];
+  }
+  Ok(total)
+}
+
+pub fn expand_env(s: &str) -> Result<String, String> {
+  let mut out = String::with_capacity(s.len());
+  let mut chars = s.char_indices().peekable();
+  while let Some((i, c)) = chars.next() {
+    match c {
+      '\\' if s[i + 1..].starts_with('$') => {
+        chars.next();
+        out.push('$');
+      }
+      '$' => {
+        let rest = &s[i + 1..];
+        let (name, len) = if let Some(braced) = rest.strip_prefix('{')
+        {
+          let end = braced
+            .find('}')
+            .ok_or_else(|| "unterminated '${'".to_string())?;
+          (&braced[..end], end + 2)
+        } else {
+          let end = rest
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+            .unwrap_or(rest.len());
+          (&rest[..end], end)
+        };
+        let valid = name
+          .chars()
+          .next()
+          .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+          && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_');
+        if !valid {
+          out.push('$');
+          continue;
+        }
+        let value = std::env::var(name).map_err(|_| {
+          format!("undefined environment variable '{}'", name)
+        })?;
+        out.push_str(&value);
+        for _ in 0..len {
+          chars.next();
+        }
+      }
+      c => out.push(c),
+    }
+  }
+  Ok(out)
+}
+
+pub fn ip_addr(s: &str) -> Result<IpAddr, String> {
+  s.parse::<IpAddr>().map_err(|e| e.to_string())
+}
+
+pub fn socket_addr(s: &str) -> Result<SocketAddr, String> {
+  s.parse::<SocketAddr>().map_err(|e| e.to_string())
+}
+
+pub fn timestamp(s: &str) -> Result<SystemTime, String> {
+  let invalid = |reason: &str| {
+    format!(
+      "invalid timestamp ({}); expected RFC 3339 like \
+       2024-01-01T00:00:00Z",
+      reason
+    )
+  };
+  let field = |range: std::ops::Range<usize>| {
+    s.get(range)
+      .filter(|f| f.bytes().all(|b| b.is_ascii_digit()))
+      .and_then(|f| f.parse::<i64>().ok())
+      .ok_or_else(|| invalid("malformed date or time"))
+  };
+  let sep = |i: usize, allowed: &[u8]| {
+    s.as_bytes()
+      .get(i)
+      .filter(|b| allowed.contains(b))
+      .map(|_| ())
+      .ok_or_else(|| invalid("malformed date or time"))
+  };
+
+  let year = field(0..4)?;
+  sep(4, b"-")?;
+  let month = field(5..7)?;
+  sep(7, b"-")?;
+  let day = field(8..10)?;
+  sep(10, b"Tt ")?;
+  let hour = field(11..13)?;
+  sep(13, b":")?;
+  let minute = field(14..16)?;
+  sep(16, b":")?;
+  let second = field(17..19)?;
+
+  let mut rest = &s[19..];
+  let mut nanos = 0;
+  if let Some(frac) = rest.strip_prefix('.') {
+    let digits = frac
+      .find(|c: char| !c.is_ascii_digit())
+      .unwrap_or(frac.len());
+    if digits == 0 {
+      return Err(invalid("empty fractional seconds"));
+    }
+    let padded = format!("{:0<9}", &frac[..digits.min(9)]);
+    nanos =
+      padded.parse::<u32>().map_err(|_| invalid("bad fraction"))?;
+    rest = &frac[digits..];
+  }
+
+  let offset = match rest {
+    "Z" | "z" => 0,
+    _ => {
+      let sign = match rest.as_bytes().first() {
+        Some(b'+') => 1,
+        Some(b'-') => -1,
+        _ => return Err(invalid("missing timezone offset")),
+      };
+      let (h, m) = rest[1..]
+        .split_once(':')
+        .filter(|(h, m)| {
+          [h, m].iter().all(|f| {
+            f.len() == 2 && f.bytes().all(|b| b.is_ascii_digit())
+          })
+        })
+        .ok_or_else(|| invalid("malformed timezone offset"))?;
+      let h = h.parse::<i64>().map_err(|_| invalid("bad offset"))?;
+      let m = m.parse::<i64>().map_err(|_| invalid("bad offset"))?;
+      if h > 23 || m > 59 {
+        return Err(invalid("timezone offset out of range"));
+      }
+      sign * (h * 3600 + m * 60)
+    }
+  };
+
+  let leap = year % 4 == 0 && (year % 100 != 0 || year % 400 == 0);
+  let days_in_month = match month {
+    1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+    4 | 6 | 9 | 11 => 30,
+    2 if leap => 29,
+    2 => 28,
+    _ => return Err(invalid("month out of range")),
+  };
+  if day < 1 || day > days_in_month {
+    return Err(invalid("day out of range"));
+  }
+  if hour > 23 || minute > 59 || second > 60 {
+    return Err(invalid("time out of range"));
+  }
+
+  // Days since the Unix epoch (Howard Hinnant's days_from_civil).
+  let y = if month <= 2 { year - 1 } else { year };
+  let era = y.div_euclid(400);
+  let yoe = y - era * 400;
+  let mp = (month + 9) % 12;
+  let doy = (153 * mp + 2) / 5 + day - 1;
+  let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+  let days = era * 146_097 + doe - 719_468;
+
+  let secs =
+    days * 86_400 + hour * 3600 + minute * 60 + second - offset;
+  let time = if secs >= 0 {
+    SystemTime::UNIX_EPOCH + Duration::new(secs as u64, nanos)
+  } else {
+    SystemTime::UNIX_EPOCH - Duration::from_secs(secs.unsigned_abs())
+      + Duration::from_nanos(nanos as u64)
+  };
+  Ok(time)
+}
+
+pub fn url(s: &str) -> Result<Url, String> {
+  s.parse::<Url>()
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Url {
+  pub scheme: String,
+  pub host: String,
+  pub port: Option<u16>,
+  pub path: String,
+  raw: String,
+}
+
+impl Url {
+  pub fn as_str(&self) -> &str {
+    &self.raw
+  }
+}
+
+impl std::str::FromStr for Url {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    let (scheme, rest) = s
+      .split_once("://")
+      .ok_or("missing scheme (e.g. https://)")?;
+    let scheme_ok = scheme
+      .chars()
+      .next()
+      .is_some_and(|c| c.is_ascii_alphabetic())
+      && scheme
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || "+-.".contains(c));
+    if !scheme_ok {
+      return Err("invalid scheme".to_string());
+    }
+
+    let end = rest.find(['/', '?', '#']).unwrap_or(rest.len());
+    let (authority, path) = rest.split_at(end);
+    let host_port = match authority.rsplit_once('@') {
+      Some((_, host_port)) => host_port,
+      None => authority,
+    };
+    let port_start = match host_port.rfind(']') {
+      Some(bracket) => {
+        host_port[bracket..].find(':').map(|i| bracket + i)
+      }
+      None => host_port.rfind(':'),
+    };
+    let (host, port) = match port_start {
+      Some(i) => {
+        let port = &host_port[i + 1..];
+        let port = port
+          .parse::<u16>()
+          .map_err(|_| "invalid port".to_string())?;
+        (&host_port[..i], Some(port))
+      }
+      None => (host_port, None),
+    };
+    if host.is_empty() {
+      return Err("missing host".to_string());
+    }
+    if host.chars().any(|c| c.is_whitespace()) {
+      return Err("invalid host".to_string());
+    }
+    match host.strip_prefix('[') {
+      Some(ip) => {
+        let ip = ip.strip_suffix(']').ok_or("invalid host")?;
+        if ip.parse::<Ipv6Addr>().is_err() {
+          return Err("invalid IPv6 address".to_string());
+        }
+      }
+      None if host.contains([':', '[', ']']) => {
+        return Err(
+          "IPv6 addresses must be in brackets (e.g. [::1])".to_string(),
+        );
+      }
+      None => {}
+    }
+
+    Ok(Url {
+      scheme: scheme.to_ascii_lowercase(),
+      host: host.to_string(),
+      port,
+      path: path.to_string(),
+      raw: s.to_string(),
+    })
+  }
+}
+
+impl fmt::Display for Url {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{}", self.raw)
+  }
+}
@@ -0,0 +1,25 @@
+use crate::color::{self, RED};
+use crate::error::OptzError;
+use crate::opt::Optz;
+use std::io::{self, IsTerminal};
+use std::process;
+
+pub fn run<F: FnOnce() -> Optz>(build: F) -> ! {
+  let optz = build();
+  let name = optz.name.clone();
+  let color = optz.color.enabled(io::stderr().is_terminal());
+  match optz.dispatch_with(|e| OptzError::Parse(e.to_string())) {
+    Ok(()) => process::exit(0),
+    Err(
+      e @ (OptzError::DisplayHelp(_) | OptzError::DisplayVersion(_)),
+    ) => {
+      print!("{}", e);
+      process::exit(0);
+    }
+    Err(e) => {
+      let error = color::paint(color, RED, &e.to_string());
+      eprintln!("{}: {}", name, error);
+      process::exit(e.exit_code());
+    }
+  }
+}
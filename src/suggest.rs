@@ -0,0 +1,35 @@
+pub(crate) fn similar<'a, I>(
+  input: &str,
+  candidates: I,
+) -> Vec<&'a str>
+where
+  I: IntoIterator<Item = &'a str>,
+{
+  let limit = (input.chars().count() / 3).max(1);
+  let mut similar = candidates
+    .into_iter()
+    .map(|candidate| (distance(input, candidate), candidate))
+    .filter(|(distance, _)| *distance <= limit)
+    .collect::<Vec<_>>();
+  similar.sort();
+  similar.dedup();
+  similar
+    .into_iter()
+    .map(|(_, candidate)| candidate)
+    .collect()
+}
+
+fn distance(a: &str, b: &str) -> usize {
+  let b = b.chars().collect::<Vec<_>>();
+  let mut row = (0..=b.len()).collect::<Vec<_>>();
+  for (i, ca) in a.chars().enumerate() {
+    let mut prev = row[0];
+    row[0] = i + 1;
+    for (j, cb) in b.iter().enumerate() {
+      let cost = if ca == *cb { prev } else { prev + 1 };
+      prev = row[j + 1];
+      row[j + 1] = cost.min(row[j] + 1).min(prev + 1);
+    }
+  }
+  row[b.len()]
+}
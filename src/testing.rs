@@ -0,0 +1,88 @@
+use crate::color::ColorChoice;
+use crate::opt::Optz;
+use std::env;
+use std::fs;
+
+pub fn render_help(optz: &Optz) -> String {
+  optz.clone().color(ColorChoice::Never).render_help()
+}
+
+pub fn pages_help(optz: &Optz, is_terminal: bool) -> bool {
+  optz.pages_help(&render_help(optz), is_terminal)
+}
+
+pub fn help_diff(optz: &Optz, path: &str) -> Option<String> {
+  let actual = render_help(optz);
+  if env::var_os("OPTZ_UPDATE_GOLDEN").is_some() {
+    fs::write(path, &actual)
+      .unwrap_or_else(|e| panic!("failed to write {}: {}", path, e));
+    return None;
+  }
+  let expected = fs::read_to_string(path).unwrap_or_else(|e| {
+    panic!(
+      "failed to read {}: {} (set OPTZ_UPDATE_GOLDEN=1 to create it)",
+      path, e
+    )
+  });
+  if expected == actual {
+    return None;
+  }
+
+  let expected_lines = expected.lines().collect::<Vec<_>>();
+  let actual_lines = actual.lines().collect::<Vec<_>>();
+  let mut diff = format!("help output does not match {}:\n", path);
+  for line in line_diff(&expected_lines, &actual_lines) {
+    diff.push_str(&line);
+    diff.push('\n');
+  }
+  if expected.ends_with('\n') != actual.ends_with('\n') {
+    diff.push_str("(trailing newline differs)\n");
+  }
+  diff.push_str(
+    "(set OPTZ_UPDATE_GOLDEN=1 to accept the new output)\n",
+  );
+  Some(diff)
+}
+
+// Longest common subsequence over lines, so an inserted or removed
+// line shows up once instead of shifting every line after it.
+fn line_diff(expected: &[&str], actual: &[&str]) -> Vec<String> {
+  let (n, m) = (expected.len(), actual.len());
+  let mut lcs = vec![vec![0; m + 1]; n + 1];
+  for i in (0..n).rev() {
+    for j in (0..m).rev() {
+      lcs[i][j] = if expected[i] == actual[j] {
+        lcs[i + 1][j + 1] + 1
+      } else {
+        lcs[i + 1][j].max(lcs[i][j + 1])
+      };
+    }
+  }
+  let mut lines = Vec::new();
+  let (mut i, mut j) = (0, 0);
+  while i < n || j < m {
+    if i < n && j < m && expected[i] == actual[j] {
+      lines.push(format!("  {}", expected[i]));
+      i += 1;
+      j += 1;
+    } else if j == m || (i < n && lcs[i + 1][j] >= lcs[i][j + 1]) {
+      lines.push(format!("- {}", expected[i]));
+      i += 1;
+    } else {
+      lines.push(format!("+ {}", actual[j]));
+      j += 1;
+    }
+  }
+  lines
+}
+
+#[macro_export]
+macro_rules! assert_help_matches {
+  ($optz:expr, $path:expr) => {
+    if let Some(diff) = $crate::testing::help_diff(&$optz, $path) {
+      panic!("{}", diff);
+    }
+  };
+}
+
+pub use crate::assert_help_matches;
@@ -0,0 +1,134 @@
+use std::sync::Arc;
+
+pub type Validator =
+  Arc<dyn Fn(&str) -> Result<(), String> + Send + Sync>;
+
+pub fn from_fn<F>(f: F) -> Validator
+where
+  F: Fn(&str) -> Result<(), String> + Send + Sync + 'static,
+{
+  Arc::new(f)
+}
+
+pub fn all(validators: Vec<Validator>) -> Validator {
+  Arc::new(move |s| validators.iter().try_for_each(|v| v(s)))
+}
+
+pub fn any(validators: Vec<Validator>) -> Validator {
+  Arc::new(move |s| {
+    let mut errors = Vec::new();
+    for v in &validators {
+      match v(s) {
+        Ok(()) => return Ok(()),
+        Err(e) => errors.push(e),
+      }
+    }
+    if errors.is_empty() {
+      return Err("no value is accepted".to_string());
+    }
+    Err(errors.join(" or "))
+  })
+}
+
+pub fn not(validator: Validator, msg: &str) -> Validator {
+  let msg = msg.to_string();
+  Arc::new(move |s| match validator(s) {
+    Ok(()) => Err(msg.clone()),
+    Err(_) => Ok(()),
+  })
+}
+
+pub fn port() -> Validator {
+  from_fn(|s| match s.parse::<u16>() {
+    Ok(port) if port > 0 => Ok(()),
+    _ => {
+      Err("expected a port number between 1 and 65535".to_string())
+    }
+  })
+}
+
+pub fn hostname() -> Validator {
+  from_fn(|s| {
+    let valid = !s.is_empty()
+      && s.len() <= 253
+      && s.trim_end_matches('.').split('.').all(|label| {
+        !label.is_empty()
+          && label.len() <= 63
+          && !label.starts_with('-')
+          && !label.ends_with('-')
+          && label
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-')
+      });
+    if valid {
+      Ok(())
+    } else {
+      Err("expected a hostname".to_string())
+    }
+  })
+}
+
+pub fn email() -> Validator {
+  from_fn(|s| {
+    let valid = match s.rsplit_once('@') {
+      Some((local, domain)) => {
+        !local.is_empty()
+          && !local.contains(char::is_whitespace)
+          && domain.contains('.')
+          && hostname()(domain).is_ok()
+      }
+      None => false,
+    };
+    if valid {
+      Ok(())
+    } else {
+      Err("expected an email address".to_string())
+    }
+  })
+}
+
+#[cfg(feature = "semver")]
+pub fn semver() -> Validator {
+  from_fn(|s| {
+    let (version, _build) = s.split_once('+').unwrap_or((s, ""));
+    let (core, pre) = match version.split_once('-') {
+      Some((core, pre)) => (core, Some(pre)),
+      None => (version, None),
+    };
+    let numeric = |part: &str| {
+      !part.is_empty()
+        && part.chars().all(|c| c.is_ascii_digit())
+        && (part == "0" || !part.starts_with('0'))
+    };
+    let identifier = |id: &str| {
+      !id.is_empty()
+        && id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+    };
+    let parts = core.split('.').collect::<Vec<_>>();
+    let valid = parts.len() == 3
+      && parts.iter().all(|p| numeric(p))
+      && pre.is_none_or(|pre| pre.split('.').all(identifier));
+    if valid {
+      Ok(())
+    } else {
+      Err("expected a semantic version like 1.2.3".to_string())
+    }
+  })
+}
+
+#[cfg(feature = "uuid")]
+pub fn uuid() -> Validator {
+  from_fn(|s| {
+    let groups = s.split('-').map(|g| g.len()).collect::<Vec<_>>();
+    let valid = groups == [8, 4, 4, 4, 12]
+      && s.chars().all(|c| c == '-' || c.is_ascii_hexdigit());
+    if valid {
+      Ok(())
+    } else {
+      Err(
+        "expected a UUID like 123e4567-e89b-12d3-a456-426614174000"
+          .to_string(),
+      )
+    }
+  })
+}
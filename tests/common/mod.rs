@@ -0,0 +1,39 @@
+use std::env;
+use std::ffi::{OsStr, OsString};
+use std::sync::{Mutex, MutexGuard};
+
+static ENV_LOCK: Mutex<()> = Mutex::new(());
+
+pub struct EnvGuard {
+  saved: Vec<(String, Option<OsString>)>,
+  _lock: MutexGuard<'static, ()>,
+}
+
+impl EnvGuard {
+  pub fn set<V: AsRef<OsStr>>(vars: &[(&str, V)]) -> Self {
+    let lock = ENV_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let saved = vars
+      .iter()
+      .map(|(name, _)| (name.to_string(), env::var_os(name)))
+      .collect();
+    for (name, value) in vars {
+      unsafe {
+        env::set_var(name, value);
+      }
+    }
+    EnvGuard { saved, _lock: lock }
+  }
+}
+
+impl Drop for EnvGuard {
+  fn drop(&mut self) {
+    for (name, value) in &self.saved {
+      unsafe {
+        match value {
+          Some(value) => env::set_var(name, value),
+          None => env::remove_var(name),
+        }
+      }
+    }
+  }
+}
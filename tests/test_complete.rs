@@ -0,0 +1,407 @@
+use optz::{Group, Opt, Optz};
+
+fn spec() -> Optz {
+  Optz::from_args("myapp", vec!["myapp"])
+    .description("My \"app\"")
+    .option(Opt::flag("verbose").short("-v").description("Verbose"))
+    .option(
+      Opt::arg("format")
+        .description("Output format")
+        .possible_values(&["json", "yaml"])
+        .requires("output"),
+    )
+    .option(Opt::arg("output").multiple(true))
+}
+
+#[test]
+fn test_fig_spec() {
+  assert_eq!(
+    spec().fig_spec(),
+    r#"{
+  "name": "myapp",
+  "description": "My \"app\"",
+  "options": [
+    {
+      "name": [
+        "--verbose",
+        "-v"
+      ],
+      "description": "Verbose"
+    },
+    {
+      "name": [
+        "--format"
+      ],
+      "description": "Output format (requires --output)",
+      "args": {
+        "name": "format",
+        "suggestions": [
+          "json",
+          "yaml"
+        ]
+      },
+      "dependsOn": [
+        "--output"
+      ]
+    },
+    {
+      "name": [
+        "--output"
+      ],
+      "description": "",
+      "args": {
+        "name": "output"
+      },
+      "isRepeatable": true
+    },
+    {
+      "name": [
+        "--help",
+        "-h"
+      ],
+      "description": "Show help"
+    }
+  ],
+  "args": {
+    "name": "args",
+    "isVariadic": true,
+    "isOptional": true
+  }
+}
+"#
+  );
+}
+
+#[test]
+fn test_carapace_spec() {
+  assert_eq!(
+    spec()
+      .option(Opt::flag("quiet").short("-q").visible_alias("--silent"))
+      .carapace_spec(),
+    r#"{
+  "name": "myapp",
+  "description": "My \"app\"",
+  "flags": {
+    "-v, --verbose": "Verbose",
+    "--format=": "Output format (requires --output)",
+    "--output=*": "",
+    "-q, --quiet": "",
+    "--silent": "",
+    "-h, --help": "Show help"
+  },
+  "completion": {
+    "flag": {
+      "format": [
+        "json",
+        "yaml"
+      ]
+    }
+  }
+}
+"#
+  );
+}
+
+#[test]
+fn test_subcommand_specs() {
+  let optz = Optz::from_args("app", vec!["app"])
+    .subcommand(Optz::new("debug").hidden(true))
+    .subcommand(
+      Optz::new("install").alias("i").description("Install"),
+    );
+  assert_eq!(
+    optz.fig_spec(),
+    r#"{
+  "name": "app",
+  "options": [
+    {
+      "name": [
+        "--help",
+        "-h"
+      ],
+      "description": "Show help"
+    }
+  ],
+  "subcommands": [
+    {
+      "name": [
+        "install",
+        "i"
+      ],
+      "description": "Install",
+      "options": [
+        {
+          "name": [
+            "--help",
+            "-h"
+          ],
+          "description": "Show help"
+        }
+      ],
+      "args": {
+        "name": "args",
+        "isVariadic": true,
+        "isOptional": true
+      }
+    }
+  ],
+  "args": {
+    "name": "args",
+    "isVariadic": true,
+    "isOptional": true
+  }
+}
+"#
+  );
+  assert_eq!(
+    optz.carapace_spec(),
+    r#"{
+  "name": "app",
+  "flags": {
+    "-h, --help": "Show help"
+  },
+  "commands": [
+    {
+      "name": "install",
+      "aliases": [
+        "i"
+      ],
+      "description": "Install",
+      "flags": {
+        "-h, --help": "Show help"
+      }
+    }
+  ]
+}
+"#
+  );
+}
+
+#[test]
+fn test_group_metadata() {
+  let optz = Optz::from_args("app", vec!["app"])
+    .option(Opt::flag("json").description("JSON"))
+    .option(Opt::flag("yaml").conflicts_with("json"))
+    .option(Opt::arg("out").requires("json"))
+    .group(Group::new("format").args(["json", "yaml"]).exclusive(true));
+  assert_eq!(
+    optz.fig_spec(),
+    r#"{
+  "name": "app",
+  "options": [
+    {
+      "name": [
+        "--json"
+      ],
+      "description": "JSON (group 'format')",
+      "exclusiveOn": [
+        "--yaml"
+      ]
+    },
+    {
+      "name": [
+        "--yaml"
+      ],
+      "description": "(conflicts with --json) (group 'format')",
+      "exclusiveOn": [
+        "--json"
+      ]
+    },
+    {
+      "name": [
+        "--out"
+      ],
+      "description": "(requires --json)",
+      "args": {
+        "name": "out"
+      },
+      "dependsOn": [
+        "--json"
+      ]
+    },
+    {
+      "name": [
+        "--help",
+        "-h"
+      ],
+      "description": "Show help"
+    }
+  ],
+  "args": {
+    "name": "args",
+    "isVariadic": true,
+    "isOptional": true
+  }
+}
+"#
+  );
+  assert_eq!(
+    optz.carapace_spec(),
+    r#"{
+  "name": "app",
+  "flags": {
+    "--json": "JSON (group 'format')",
+    "--yaml": "(conflicts with --json) (group 'format')",
+    "--out=": "(requires --json)",
+    "-h, --help": "Show help"
+  }
+}
+"#
+  );
+}
+
+#[test]
+fn test_specs_include_builtin_options() {
+  let optz = Optz::from_args("app", vec!["app"])
+    .version("1.0")
+    .config_flag(true)
+    .subcommand(Optz::new("beta").experimental(true));
+  let carapace = optz.carapace_spec();
+  for flag in [
+    "\"-V, --version\"",
+    "\"--config=\"",
+    "\"--enable-experimental\"",
+    "\"-h, --help\"",
+  ] {
+    assert!(carapace.contains(flag), "{} missing", flag);
+  }
+  assert!(optz.fig_spec().contains("\"--enable-experimental\""));
+}
+
+#[test]
+fn test_zsh_completion() {
+  assert_eq!(
+    spec().zsh_completion(),
+    r#"#compdef myapp
+
+_myapp() {
+  _arguments -s -S \
+    '(--verbose -v)'{--verbose,-v}'[Verbose]' \
+    '(--format)--format=[Output format (requires --output)]:format:(json yaml)' \
+    '*--output=[]:output:' \
+    '(--help -h)'{--help,-h}'[Show help]'
+}
+
+_myapp "$@"
+"#
+  );
+}
+
+#[test]
+fn test_fish_completion() {
+  assert_eq!(
+    spec().fish_completion(),
+    "complete -c myapp -l verbose -s v -d 'Verbose'\n\
+     complete -c myapp -l format -r -f -a 'json yaml' \
+     -d 'Output format (requires --output)'\n\
+     complete -c myapp -l output -r\n\
+     complete -c myapp -l help -s h -d 'Show help'\n"
+  );
+}
+
+#[test]
+fn test_completion_scripts_with_subcommands() {
+  let optz = Optz::from_args("app", vec!["app"])
+    .option(Opt::flag("json"))
+    .option(Opt::flag("yaml"))
+    .group(Group::new("format").args(["json", "yaml"]).exclusive(true))
+    .subcommand(
+      Optz::new("install").alias("i").description("Install"),
+    );
+  assert_eq!(
+    optz.zsh_completion(),
+    r#"#compdef app
+
+_app() {
+  local line state
+  _arguments -s -S \
+    '(--json --yaml)--json[(group '\''format'\'')]' \
+    '(--yaml --json)--yaml[(group '\''format'\'')]' \
+    '(--help -h)'{--help,-h}'[Show help]' \
+    '1: :_app_commands' \
+    '*::arg:->args'
+  case $state in
+    args)
+      case $line[1] in
+        'install'|'i') _app_install ;;
+      esac
+      ;;
+  esac
+}
+
+_app_commands() {
+  local commands=(
+    'install:Install'
+    'i:Install'
+  )
+  _describe 'command' commands
+}
+
+_app_install() {
+  _arguments -s -S \
+    '(--help -h)'{--help,-h}'[Show help]'
+}
+
+_app "$@"
+"#
+  );
+  assert_eq!(
+    optz.fish_completion(),
+    "complete -c app -l json -d '(group \\'format\\')'\n\
+     complete -c app -l yaml -d '(group \\'format\\')'\n\
+     complete -c app -l help -s h -d 'Show help'\n\
+     complete -c app -n '__fish_use_subcommand' -f -a 'install' \
+     -d 'Install'\n\
+     complete -c app -n '__fish_use_subcommand' -f -a 'i' -d 'Install'\n\
+     complete -c app -n '__fish_seen_subcommand_from install i' \
+     -l help -s h -d 'Show help'\n"
+  );
+}
+
+#[test]
+fn test_fig_positional_args() {
+  let optz = Optz::from_args("cp", vec!["cp"])
+    .option(Opt::positional("dest").required(true).index(2))
+    .option(
+      Opt::positional("sources")
+        .description("Files to copy")
+        .many(1..)
+        .index(1),
+    )
+    .option(
+      Opt::positional("mode")
+        .possible_values(&["fast", "safe"])
+        .index(3),
+    )
+    .option(Opt::positional("extra").many(..).index(4));
+  let spec = optz.fig_spec();
+  let args = &spec[spec.rfind("\"args\"").unwrap()..];
+  assert_eq!(
+    args,
+    r#""args": [
+    {
+      "name": "sources",
+      "description": "Files to copy",
+      "isVariadic": true
+    },
+    {
+      "name": "dest"
+    },
+    {
+      "name": "mode",
+      "suggestions": [
+        "fast",
+        "safe"
+      ],
+      "isOptional": true
+    },
+    {
+      "name": "extra",
+      "isVariadic": true,
+      "isOptional": true
+    }
+  ]
+}
+"#
+  );
+}
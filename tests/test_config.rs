@@ -0,0 +1,413 @@
+#![cfg(any(
+  feature = "config-toml",
+  feature = "config-json",
+  feature = "config-yaml"
+))]
+
+#[cfg(feature = "config-toml")]
+use optz::AppDirs;
+use optz::{Messages, Opt, Optz, OptzError, ValueSource};
+use std::fs;
+use std::path::PathBuf;
+
+#[cfg(feature = "config-toml")]
+mod common;
+
+fn write_config(name: &str, text: &str) -> PathBuf {
+  let path = std::env::temp_dir().join(format!(
+    "optz-{}-{}",
+    std::process::id(),
+    name
+  ));
+  fs::write(&path, text).unwrap();
+  path
+}
+
+#[cfg(feature = "config-toml")]
+#[test]
+fn test_toml_config_file() {
+  let path = write_config(
+    "layers.toml",
+    "# defaults for myapp\n\
+     port = 8080\n\
+     host = \"example.com\"\n\
+     verbose = true\n\
+     tags = [\"a\", \"b\"]\n\
+     \n\
+     [build]\n\
+     jobs = 4\n",
+  );
+  let _env = common::EnvGuard::set(&[
+    ("OPTZ_CONFIG_HOST", "env.example.com"),
+  ]);
+  let spec = Optz::from_args("myapp", vec!["myapp"])
+    .config_file(path.to_str().unwrap())
+    .option(Opt::arg("port").default_value("80"))
+    .option(Opt::arg("host").env("OPTZ_CONFIG_HOST"))
+    .option(Opt::flag("verbose"))
+    .option(Opt::arg("tags").multiple(true))
+    .option(Opt::arg("user").default_value("nobody"))
+    .subcommand(Optz::new("build").option(Opt::arg("jobs")));
+  let optz = spec.parse_from(vec!["myapp", "build"]).unwrap();
+  assert_eq!(optz.value_of("port"), Some("8080"));
+  assert_eq!(optz.source_of("port"), Some(ValueSource::ConfigFile));
+  assert_eq!(optz.value_of("host"), Some("env.example.com"));
+  assert!(optz.has("verbose").unwrap());
+  assert_eq!(optz.values_of("tags").collect::<Vec<_>>(), ["a", "b"]);
+  assert_eq!(optz.value_of("user"), Some("nobody"));
+  let (_, build) = optz.subcommand().unwrap();
+  assert_eq!(build.value_of("jobs"), Some("4"));
+  let optz =
+    spec.parse_from(vec!["myapp", "--port", "9000"]).unwrap();
+  assert_eq!(optz.value_of("port"), Some("9000"));
+  fs::remove_file(path).unwrap();
+}
+
+#[cfg(feature = "config-toml")]
+#[test]
+fn test_config_flag() {
+  let path = write_config("flag.toml", "log-level = 'debug'\n");
+  let spec = Optz::from_args("myapp", vec!["myapp"])
+    .config_file("/nonexistent/optz/config.toml")
+    .config_flag(true)
+    .option(Opt::arg("log-level").default_value("info"));
+  assert!(spec.render_help().starts_with(
+    "Usage: myapp [--log-level <log-level>] [--config <path>]\n"
+  ));
+  let optz = spec.parse_from(vec!["myapp"]).unwrap();
+  assert_eq!(optz.value_of("log-level"), Some("info"));
+  let optz = spec
+    .parse_from(vec!["myapp", "--config", path.to_str().unwrap()])
+    .unwrap();
+  assert_eq!(optz.value_of("log-level"), Some("debug"));
+  let result =
+    spec.parse_from(vec!["myapp", "--config", "missing.toml"]);
+  if let Err(OptzError::InvalidValue(msg)) = result {
+    assert!(msg.starts_with("cannot read missing.toml: "));
+  } else {
+    panic!("Unexpected result");
+  }
+  fs::remove_file(path).unwrap();
+}
+
+#[cfg(feature = "config-toml")]
+#[test]
+fn test_config_debug_is_redacted() {
+  let path =
+    write_config("secret.toml", "token = \"hunter2\"\nport = 1\n");
+  let optz = Optz::from_args("test", vec!["test"])
+    .config_file(path.to_str().unwrap())
+    .option(Opt::arg("token").secret(true))
+    .option(Opt::arg("port"))
+    .parse()
+    .unwrap();
+  assert_eq!(optz.value_of("token"), Some("hunter2"));
+  let debug = format!("{:?}", optz);
+  assert!(!debug.contains("hunter2"));
+  assert_eq!(optz.source_of("token"), Some(ValueSource::ConfigFile));
+  fs::remove_file(path).unwrap();
+}
+
+#[cfg(feature = "config-toml")]
+#[test]
+fn test_toml_syntax() {
+  let path = write_config(
+    "syntax.toml",
+    "title = \"tab\\there \\u00e9\"\n\
+     raw = 'C:\\path'\n\
+     text = \"\"\"\n\
+     one \\\n\
+     \x20   two\"\"\"\n\
+     size = 1_000\n\
+     when = 1979-05-27 07:32:00Z\n\
+     list = [\n\
+     \x20 1, # first\n\
+     \x20 2,\n\
+     ]\n\
+     server.name = \"alpha\"\n\
+     point = { x = 1, y = 2 }\n",
+  );
+  let optz = Optz::from_args("test", vec!["test"])
+    .config_file(path.to_str().unwrap())
+    .option(Opt::arg("title"))
+    .option(Opt::arg("raw"))
+    .option(Opt::arg("text"))
+    .option(Opt::arg("size"))
+    .option(Opt::arg("when"))
+    .option(Opt::arg("list").multiple(true))
+    .subcommand(Optz::new("server").option(Opt::arg("name")))
+    .parse_from(vec!["test", "server"])
+    .unwrap();
+  assert_eq!(optz.value_of("title"), Some("tab\there é"));
+  assert_eq!(optz.value_of("raw"), Some("C:\\path"));
+  assert_eq!(optz.value_of("text"), Some("one two"));
+  assert_eq!(optz.value_of("size"), Some("1000"));
+  assert_eq!(optz.value_of("when"), Some("1979-05-27T07:32:00Z"));
+  assert_eq!(optz.values_of("list").collect::<Vec<_>>(), ["1", "2"]);
+  let (_, server) = optz.subcommand().unwrap();
+  assert_eq!(server.value_of("name"), Some("alpha"));
+  fs::remove_file(path).unwrap();
+}
+
+#[cfg(feature = "config-toml")]
+#[test]
+fn test_toml_errors() {
+  let error = |name: &str, text: &str| {
+    let path = write_config(name, text);
+    let result = Optz::from_args("test", vec!["test"])
+      .config_file(path.to_str().unwrap())
+      .parse();
+    fs::remove_file(&path).unwrap();
+    match result {
+      Err(OptzError::InvalidValue(msg)) => {
+        msg.replace(&format!("{}: ", path.display()), "")
+      }
+      _ => panic!("Unexpected result"),
+    }
+  };
+  let missing = error("missing.toml", "a = 1\nb 2\n");
+  assert!(
+    missing.starts_with("TOML parse error at line 2, column 3")
+  );
+  assert!(missing.ends_with("key with no value, expected `=`"));
+  let duplicate = error("duplicate.toml", "a = 1\na = 2\n");
+  assert!(duplicate.starts_with("TOML parse error at line 2"));
+  assert!(duplicate.ends_with("duplicate key"));
+  let bare = error("bare.toml", "a = yes\n");
+  assert!(bare.ends_with(
+    "string values must be quoted, expected literal string"
+  ));
+  assert_eq!(
+    error("nested.toml", "a = [[1]]\n"),
+    "nested lists and tables are not supported"
+  );
+}
+
+#[cfg(feature = "config-toml")]
+#[test]
+fn test_config_search() {
+  let root = std::env::temp_dir()
+    .join(format!("optz-{}-search", std::process::id()));
+  fs::create_dir_all(root.join("myapp")).unwrap();
+  fs::create_dir_all(root.join("other")).unwrap();
+  fs::write(root.join("myapp/config.toml"), "port = 8080\n").unwrap();
+  fs::write(root.join("other/settings.toml"), "port = 9090\n")
+    .unwrap();
+  let _env = common::EnvGuard::set(&[("XDG_CONFIG_HOME", &root)]);
+  let port = |dirs: AppDirs, file: Option<&str>| {
+    let mut spec = Optz::from_args("myapp", vec!["myapp"])
+      .config_search(dirs)
+      .option(Opt::arg("port").default_value("80"));
+    if let Some(file) = file {
+      spec = spec.config_file(file);
+    }
+    spec.parse().unwrap().value_of("port").map(str::to_string)
+  };
+  assert_eq!(port(AppDirs::default(), None), Some("8080".into()));
+  assert_eq!(
+    port(AppDirs::default().app_name("other"), None),
+    Some("80".into())
+  );
+  assert_eq!(
+    port(
+      AppDirs::default().app_name("other").file_stem("settings"),
+      None
+    ),
+    Some("9090".into())
+  );
+  let path = write_config("explicit.toml", "port = 7070\n");
+  assert_eq!(
+    port(AppDirs::default(), path.to_str()),
+    Some("7070".into())
+  );
+  assert_eq!(
+    port(AppDirs::default(), Some("/nonexistent/optz/config.toml")),
+    Some("8080".into())
+  );
+  fs::remove_file(path).unwrap();
+  fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn test_unsupported_config_format() {
+  let path = write_config("config.ini", "a = 1\n");
+  let result = Optz::from_args("test", vec!["test"])
+    .config_file(path.to_str().unwrap())
+    .parse();
+  if let Err(OptzError::InvalidValue(msg)) = result {
+    assert_eq!(
+      msg,
+      format!(
+        "unsupported config format 'ini' for {}",
+        path.display()
+      )
+    );
+  } else {
+    panic!("Unexpected result");
+  }
+  let messages = Messages {
+    config_format: "formato '{}' no admitido: {}".to_string(),
+    ..Messages::default()
+  };
+  let result = Optz::from_args("test", vec!["test"])
+    .messages(messages)
+    .config_file(path.to_str().unwrap())
+    .parse();
+  if let Err(OptzError::InvalidValue(msg)) = result {
+    assert_eq!(
+      msg,
+      format!("formato 'ini' no admitido: {}", path.display())
+    );
+  } else {
+    panic!("Unexpected result");
+  }
+  fs::remove_file(path).unwrap();
+}
+
+#[cfg(feature = "config-json")]
+#[test]
+fn test_json_config_file() {
+  let path = write_config(
+    "layers.json",
+    "{\n\
+     \x20 \"port\": 8080,\n\
+     \x20 \"host\": \"caf\\u00e9.example.com\",\n\
+     \x20 \"verbose\": true,\n\
+     \x20 \"user\": null,\n\
+     \x20 \"tags\": [\"a\", \"b\"],\n\
+     \x20 \"build\": { \"jobs\": 4 }\n\
+     }\n",
+  );
+  let optz = Optz::from_args("myapp", vec!["myapp"])
+    .config_file(path.to_str().unwrap())
+    .option(Opt::arg("port").default_value("80"))
+    .option(Opt::arg("host"))
+    .option(Opt::flag("verbose"))
+    .option(Opt::arg("user").default_value("nobody"))
+    .option(Opt::arg("tags").multiple(true))
+    .subcommand(Optz::new("build").option(Opt::arg("jobs")))
+    .parse_from(vec!["myapp", "build"])
+    .unwrap();
+  assert_eq!(optz.value_of("port"), Some("8080"));
+  assert_eq!(optz.source_of("port"), Some(ValueSource::ConfigFile));
+  assert_eq!(optz.value_of("host"), Some("café.example.com"));
+  assert!(optz.has("verbose").unwrap());
+  assert_eq!(optz.value_of("user"), Some("nobody"));
+  assert_eq!(optz.values_of("tags").collect::<Vec<_>>(), ["a", "b"]);
+  let (_, build) = optz.subcommand().unwrap();
+  assert_eq!(build.value_of("jobs"), Some("4"));
+  fs::remove_file(path).unwrap();
+}
+
+#[cfg(feature = "config-json")]
+#[test]
+fn test_json_errors() {
+  let error = |name: &str, text: &str| {
+    let path = write_config(name, text);
+    let result = Optz::from_args("test", vec!["test"])
+      .config_file(path.to_str().unwrap())
+      .parse();
+    fs::remove_file(&path).unwrap();
+    match result {
+      Err(OptzError::InvalidValue(msg)) => {
+        msg.replace(&format!("{}: ", path.display()), "")
+      }
+      _ => panic!("Unexpected result"),
+    }
+  };
+  assert_eq!(
+    error("array.json", "[1, 2]"),
+    "expected a table at the top level"
+  );
+  assert_eq!(
+    error("comma.json", "{\n\"a\": 1\n\"b\": 2\n}"),
+    "expected `,` or `}` at line 3 column 1"
+  );
+  assert_eq!(
+    error("nested.json", "{\"a\": [[1]]}"),
+    "nested lists and tables are not supported"
+  );
+}
+
+#[cfg(feature = "config-yaml")]
+#[test]
+fn test_yaml_config_file() {
+  let path = write_config(
+    "layers.yaml",
+    "---\n\
+     # defaults for myapp\n\
+     port: 8080\n\
+     host: \"example.com\" # primary\n\
+     log_level: 'it''s'\n\
+     user: ~\n\
+     tags:\n\
+     \x20 - a\n\
+     \x20 - \"b c\"\n\
+     ports: [80, 443]\n\
+     build:\n\
+     \x20 jobs: 4\n",
+  );
+  let optz = Optz::from_args("myapp", vec!["myapp"])
+    .config_file(path.to_str().unwrap())
+    .option(Opt::arg("port").default_value("80"))
+    .option(Opt::arg("host"))
+    .option(Opt::arg("log-level"))
+    .option(Opt::arg("user").default_value("nobody"))
+    .option(Opt::arg("tags").multiple(true))
+    .option(Opt::arg("ports").multiple(true))
+    .subcommand(Optz::new("build").option(Opt::arg("jobs")))
+    .parse_from(vec!["myapp", "build"])
+    .unwrap();
+  assert_eq!(optz.value_of("port"), Some("8080"));
+  assert_eq!(optz.source_of("port"), Some(ValueSource::ConfigFile));
+  assert_eq!(optz.value_of("host"), Some("example.com"));
+  assert_eq!(optz.value_of("log-level"), Some("it's"));
+  assert_eq!(optz.value_of("user"), Some("nobody"));
+  assert_eq!(optz.values_of("tags").collect::<Vec<_>>(), ["a", "b c"]);
+  assert_eq!(
+    optz.values_of("ports").collect::<Vec<_>>(),
+    ["80", "443"]
+  );
+  let (_, build) = optz.subcommand().unwrap();
+  assert_eq!(build.value_of("jobs"), Some("4"));
+  fs::remove_file(path).unwrap();
+}
+
+#[cfg(feature = "config-yaml")]
+#[test]
+fn test_yaml_errors() {
+  let error = |name: &str, text: &str| {
+    let path = write_config(name, text);
+    let result = Optz::from_args("test", vec!["test"])
+      .config_file(path.to_str().unwrap())
+      .parse();
+    fs::remove_file(&path).unwrap();
+    match result {
+      Err(OptzError::InvalidValue(msg)) => {
+        msg.replace(&format!("{}: ", path.display()), "")
+      }
+      _ => panic!("Unexpected result"),
+    }
+  };
+  assert_eq!(
+    error("missing.yml", "a: 1\nb\n"),
+    "could not find expected ':' at line 3 column 1, while scanning \
+     a simple key at line 2 column 1"
+  );
+  assert_eq!(
+    error("duplicate.yml", "a: 1\na: 2\n"),
+    "duplicate entry with key \"a\""
+  );
+  assert_eq!(
+    error("indent.yml", "a: 1\n  b: 2\n"),
+    "mapping values are not allowed in this context at line 2 column 4"
+  );
+  assert_eq!(
+    error("nested.yml", "a:\n  - b: 1\n"),
+    "nested lists and tables are not supported"
+  );
+  assert_eq!(
+    error("list.yml", "- a\n"),
+    "expected a table at the top level"
+  );
+}
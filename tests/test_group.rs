@@ -0,0 +1,138 @@
+use optz::{Group, Opt, Optz, OptzError};
+
+#[test]
+fn test_exclusive_group() {
+  let result =
+    Optz::from_args("test", vec!["test", "--json", "--yaml"])
+      .option(Opt::flag("json"))
+      .option(Opt::flag("yaml"))
+      .group(
+        Group::new("output").args(["json", "yaml"]).exclusive(true),
+      )
+      .parse();
+  if let Err(OptzError::Conflict(msg)) = result {
+    assert_eq!(
+      msg,
+      "--json cannot be used with --yaml (group 'output')"
+    );
+  } else {
+    panic!("Unexpected result");
+  }
+}
+
+#[test]
+fn test_exclusive_group_ignores_false_flags() {
+  let optz =
+    Optz::from_args("test", vec!["test", "--json=false", "--yaml"])
+      .option(Opt::flag("json"))
+      .option(Opt::flag("yaml"))
+      .group(
+        Group::new("output").args(["json", "yaml"]).exclusive(true),
+      )
+      .parse()
+      .unwrap();
+  assert!(optz.has("yaml").unwrap());
+}
+
+#[test]
+fn test_exclusive_group_ignores_defaults() {
+  let optz = Optz::from_args("test", vec!["test", "--json"])
+    .option(Opt::flag("json"))
+    .option(Opt::arg("yaml").default_value("x"))
+    .group(
+      Group::new("output").args(["json", "yaml"]).exclusive(true),
+    )
+    .parse()
+    .unwrap();
+  assert!(optz.has("json").unwrap());
+}
+
+#[test]
+fn test_requires() {
+  let result = Optz::from_args("test", vec!["test", "--compress"])
+    .option(Opt::flag("compress").requires("output"))
+    .option(Opt::arg("output"))
+    .parse();
+  if let Err(OptzError::MissingRequired(msg)) = result {
+    assert_eq!(msg, "--compress requires --output");
+  } else {
+    panic!("Unexpected result");
+  }
+
+  let optz = Optz::from_args("test", vec!["test", "--compress"])
+    .option(Opt::flag("compress").requires("output"))
+    .option(Opt::arg("output").default_value("out.gz"))
+    .parse()
+    .unwrap();
+  assert!(optz.has("compress").unwrap());
+}
+
+#[test]
+fn test_conflicts_with() {
+  let result =
+    Optz::from_args("test", vec!["test", "--verbose", "--quiet"])
+      .option(Opt::flag("verbose").conflicts_with("quiet"))
+      .option(Opt::flag("quiet"))
+      .parse();
+  if let Err(OptzError::Conflict(msg)) = result {
+    assert_eq!(msg, "--verbose cannot be used with --quiet");
+  } else {
+    panic!("Unexpected result");
+  }
+}
+
+#[test]
+fn test_required_group() {
+  let spec = Optz::from_args("test", vec!["test"])
+    .option(Opt::arg("file"))
+    .option(Opt::flag("stdin"))
+    .group(Group::new("input").args(["file", "stdin"]).required(true));
+  let result = spec.clone().parse();
+  if let Err(OptzError::MissingRequired(msg)) = result {
+    assert_eq!(msg, "one of --file, --stdin is required (group 'input')");
+  } else {
+    panic!("Unexpected result");
+  }
+
+  let mut spec = spec;
+  spec.args = vec!["--stdin".to_string()];
+  assert!(spec.parse().is_ok());
+}
+
+#[test]
+fn test_required_if_eq() {
+  let result =
+    Optz::from_args("test", vec!["test", "--mode", "remote"])
+      .option(Opt::arg("mode"))
+      .option(Opt::arg("host").required_if_eq("mode", "remote"))
+      .parse();
+  if let Err(OptzError::MissingRequired(msg)) = result {
+    assert_eq!(msg, "--host is required when --mode is remote");
+  } else {
+    panic!("Unexpected result");
+  }
+
+  let optz = Optz::from_args("test", vec!["test", "--mode", "local"])
+    .option(Opt::arg("mode"))
+    .option(Opt::arg("host").required_if_eq("mode", "remote"))
+    .parse()
+    .unwrap();
+  assert_eq!(optz.get::<String>("host").unwrap(), None);
+}
+
+#[test]
+fn test_exclusive_option() {
+  let spec = Optz::from_args("test", vec!["test", "--version", "-v"])
+    .option(Opt::flag("version").exclusive(true))
+    .option(Opt::flag("verbose").short("-v"));
+  let result = spec.clone().parse();
+  if let Err(OptzError::Conflict(msg)) = result {
+    assert_eq!(msg, "--version cannot be used with other arguments");
+  } else {
+    panic!("Unexpected result");
+  }
+
+  let mut spec = spec;
+  spec.args = vec!["--version".to_string()];
+  assert!(spec.parse().unwrap().has("version").unwrap());
+}
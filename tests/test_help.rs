@@ -0,0 +1,695 @@
+use optz::testing;
+use optz::{
+  ColorChoice, Messages, Opt, Optz, OptzError, Section, Sort,
+};
+
+mod common;
+
+#[test]
+fn test_long_about_sections() {
+  let optz = Optz::from_args("test", vec!["test"]).long_about(
+    "A tool that does things.\n\
+     \n\
+     # Examples\n\
+     \n\
+     test --verbose\n\
+     \n\
+     ## Environment\n\
+     TEST_LOG  Log level\n",
+  );
+  assert_eq!(
+    optz.long_about,
+    vec![
+      Section {
+        title: "About".to_string(),
+        body: "A tool that does things.".to_string(),
+      },
+      Section {
+        title: "Examples".to_string(),
+        body: "test --verbose".to_string(),
+      },
+      Section {
+        title: "Environment".to_string(),
+        body: "TEST_LOG  Log level".to_string(),
+      },
+    ]
+  );
+}
+
+#[test]
+fn test_help_translator() {
+  fn translate(s: &str) -> String {
+    match s {
+      "Usage: test [--old] <file>" => "Uso: test [--old] <file>",
+      "Arguments:" => "Argumentos:",
+      "Options:" => "Opciones:",
+      "Show help" => "Mostrar ayuda",
+      "Input file" => "Archivo de entrada",
+      "deprecated" => "obsoleto",
+      s => s,
+    }
+    .to_string()
+  }
+  let optz = Optz::from_args("test", vec!["test"])
+    .help_translator(translate)
+    .option(
+      Opt::positional("file")
+        .description("Input file")
+        .required(true),
+    )
+    .option(Opt::flag("old").deprecated("use --new"));
+  assert_eq!(
+    testing::render_help(&optz),
+    "Uso: test [--old] <file>\n\
+     \n\
+     Argumentos:\n\
+     \x20 <file>           Archivo de entrada\n\
+     \n\
+     Opciones:\n\
+     \x20     --old         [obsoleto]\n\
+     \x20 -h, --help       Mostrar ayuda\n"
+  );
+}
+
+#[test]
+fn test_version_flag() {
+  let optz = Optz::from_args("test", vec!["test"]).version("1.2.3");
+  assert_eq!(
+    testing::render_help(&optz),
+    "test 1.2.3\n\
+     Usage: test\n\
+     \x20 -V, --version    Show version\n\
+     \x20 -h, --help       Show help\n"
+  );
+
+  let optz = Optz::from_args("test", vec!["test", "-V"])
+    .version("1.2.3")
+    .option(Opt::flag("verbose").short("-V"))
+    .parse()
+    .unwrap();
+  assert!(optz.is_present("verbose"));
+  assert!(!optz.is_present("version"));
+}
+
+#[test]
+fn test_renamed_from_in_help() {
+  let optz = Optz::from_args("test", vec!["test"]).option(
+    Opt::flag("dry-run")
+      .description("Do nothing")
+      .renamed_from("dry"),
+  );
+  assert_eq!(
+    testing::render_help(&optz),
+    "Usage: test [--dry-run]\n\
+     \x20     --dry-run    Do nothing [renamed from --dry]\n\
+     \x20 -h, --help       Show help\n"
+  );
+  let optz = Optz::from_args("tool", vec!["tool"]).subcommand(
+    Optz::new("pull")
+      .description("Fetch and merge")
+      .renamed_from("fetch"),
+  );
+  assert_eq!(
+    testing::render_help(&optz),
+    "Usage: tool [<command>]\n\
+     \x20 -h, --help       Show help\n\
+     \n\
+     Commands:\n\
+     \x20 pull             Fetch and merge [renamed from fetch]\n\
+     \x20 help             Show help for a command\n"
+  );
+}
+
+#[test]
+fn test_description_and_authors() {
+  let mut optz = Optz::from_args("test", vec!["test"])
+    .description("Does things.")
+    .option(Opt::flag("quiet").description("Be quiet"));
+  optz.authors = vec!["Ann <ann@example.com>".to_string()];
+  assert_eq!(
+    testing::render_help(&optz),
+    "Usage: test [--quiet]\n\
+     \n\
+     Does things.\n\
+     \n\
+     \x20     --quiet      Be quiet\n\
+     \x20 -h, --help       Show help\n\
+     \n\
+     Authors: Ann <ann@example.com>\n"
+  );
+  let optz = optz.hide_description(true).hide_authors(true);
+  assert_eq!(
+    testing::render_help(&optz),
+    "Usage: test [--quiet]\n\
+     \x20     --quiet      Be quiet\n\
+     \x20 -h, --help       Show help\n"
+  );
+}
+
+#[test]
+fn test_value_name() {
+  let optz = Optz::from_args("test", vec!["test"]).option(
+    Opt::arg("num-items")
+      .value_name("N")
+      .description("Number of items"),
+  );
+  assert_eq!(
+    testing::render_help(&optz),
+    "Usage: test [--num-items <N>]\n\
+     \x20     --num-items <N>  Number of items\n\
+     \x20 -h, --help           Show help\n"
+  );
+}
+
+#[test]
+fn test_help_annotations() {
+  let optz = Optz::from_args("test", vec!["test"])
+    .option(Opt::arg("count").description("Count").default_value("5"))
+    .option(
+      Opt::arg("format")
+        .description("Format")
+        .possible_values(&["json", "yaml"]),
+    );
+  assert_eq!(
+    testing::render_help(&optz),
+    "Usage: test [--count <count>] [--format <format>]\n\
+     \x20     --count <count>    Count [default: 5]\n\
+     \x20     --format <format>  Format [possible: json|yaml]\n\
+     \x20 -h, --help             Show help\n"
+  );
+  assert_eq!(
+    testing::render_help(&optz.hide_annotations(true)),
+    "Usage: test [--count <count>] [--format <format>]\n\
+     \x20     --count <count>    Count\n\
+     \x20     --format <format>  Format\n\
+     \x20 -h, --help             Show help\n"
+  );
+}
+
+#[test]
+fn test_color() {
+  let spec = || {
+    Optz::from_args("test", vec!["test"])
+      .option(Opt::positional("file"))
+      .option(Opt::flag("quiet").short("-q").description("Be quiet"))
+  };
+  assert_eq!(
+    spec().color(ColorChoice::Always).render_help(),
+    "Usage: test [-q] [<file>]\n\
+     \n\
+     \x1b[1mArguments:\x1b[0m\n\
+     \x20 \x1b[32m<file>\x1b[0m\n\
+     \n\
+     \x1b[1mOptions:\x1b[0m\n\
+     \x20 \x1b[32m-q\x1b[0m, \x1b[32m--quiet\x1b[0m      Be quiet\n\
+     \x20 \x1b[32m-h\x1b[0m, \x1b[32m--help\x1b[0m       Show help\n"
+  );
+  assert!(
+    !testing::render_help(&spec().color(ColorChoice::Never))
+      .contains('\x1b')
+  );
+}
+
+#[test]
+fn test_color_ignores_env() {
+  let _env = common::EnvGuard::set(&[("CLICOLOR_FORCE", "1")]);
+  let optz = Optz::from_args("test", vec!["test", "--help"])
+    .option(Opt::flag("quiet").description("Be quiet"))
+    .return_help(true);
+  assert!(!optz.render_help().contains('\x1b'));
+  assert!(!optz.render_long_help().contains('\x1b'));
+  if let Err(OptzError::DisplayHelp(help)) = optz.parse() {
+    assert!(!help.contains('\x1b'));
+  } else {
+    panic!("Unexpected result");
+  }
+}
+
+#[test]
+fn test_paged_help_only_on_terminal() {
+  let env = common::EnvGuard::set(&[("LINES", "2")]);
+  let optz = Optz::from_args("test", vec!["test"])
+    .option(Opt::flag("quiet").description("Be quiet"))
+    .paged_help(true);
+  assert!(testing::pages_help(&optz, true));
+  assert!(!testing::pages_help(&optz, false));
+  assert!(!testing::pages_help(&optz.clone().paged_help(false), true));
+  drop(env);
+
+  let _env = common::EnvGuard::set(&[("LINES", "50")]);
+  assert!(!testing::pages_help(&optz, true));
+}
+
+#[test]
+fn test_render_and_write_help() {
+  let optz = Optz::from_args("test", vec!["test"])
+    .option(Opt::flag("quiet").description("Be quiet"));
+  let expected = "Usage: test [--quiet]\n\
+                  \x20     --quiet      Be quiet\n\
+                  \x20 -h, --help       Show help\n";
+  assert_eq!(optz.render_help(), expected);
+  let mut out = Vec::new();
+  optz.write_help(&mut out).unwrap();
+  assert_eq!(String::from_utf8(out).unwrap(), expected);
+}
+
+#[test]
+fn test_help_template() {
+  let optz = Optz::from_args("test", vec!["test"])
+    .version("1.0")
+    .description("Does things.")
+    .help_template(
+      "{usage}\n\nFLAGS:\n{options}\n\n{version} {unknown}",
+    )
+    .option(Opt::flag("quiet").description("Be quiet"));
+  assert_eq!(
+    optz.render_help(),
+    "Usage: test [--quiet]\n\
+     \n\
+     FLAGS:\n\
+     \x20     --quiet      Be quiet\n\
+     \x20 -V, --version    Show version\n\
+     \x20 -h, --help       Show help\n\
+     \n\
+     test 1.0 {unknown}\n"
+  );
+}
+
+#[test]
+fn test_before_after_help_and_examples() {
+  let optz = Optz::from_args("test", vec!["test"])
+    .before_help("test - a tool")
+    .after_help("See the manual for more.")
+    .examples(&[
+      ("test --quiet", "Run quietly"),
+      ("test", "Run with defaults"),
+    ])
+    .option(Opt::flag("quiet").description("Be quiet"));
+  assert_eq!(
+    optz.render_help(),
+    "test - a tool\n\
+     \n\
+     Usage: test [--quiet]\n\
+     \x20     --quiet      Be quiet\n\
+     \x20 -h, --help       Show help\n\
+     \n\
+     EXAMPLES:\n\
+     \x20 Run quietly\n\
+     \x20   $ test --quiet\n\
+     \n\
+     \x20 Run with defaults\n\
+     \x20   $ test\n\
+     \n\
+     See the manual for more.\n"
+  );
+}
+
+#[test]
+fn test_help_headings() {
+  let optz = Optz::from_args("test", vec!["test"])
+    .option(
+      Opt::arg("port")
+        .help_heading("Networking")
+        .description("Port"),
+    )
+    .option(Opt::flag("quiet").description("Be quiet"))
+    .option(
+      Opt::flag("trace")
+        .help_heading("Debugging")
+        .description("Trace"),
+    )
+    .option(
+      Opt::arg("host")
+        .help_heading("Networking")
+        .description("Host"),
+    );
+  assert_eq!(
+    optz.hide_annotations(true).render_help(),
+    "Usage: test [--port <port>] [--quiet] [--trace] [--host <host>]\n\
+     \x20     --quiet        Be quiet\n\
+     \x20 -h, --help         Show help\n\
+     \n\
+     Networking:\n\
+     \x20     --port <port>  Port\n\
+     \x20     --host <host>  Host\n\
+     \n\
+     Debugging:\n\
+     \x20     --trace        Trace\n"
+  );
+}
+
+#[test]
+fn test_hidden_and_sort() {
+  let optz = Optz::from_args("test", vec!["test"])
+    .option(Opt::flag("verbose").description("Verbose"))
+    .option(Opt::flag("debug-dump").hidden(true))
+    .option(Opt::flag("all").description("All"))
+    .option(Opt::flag("zap").description("Zap").display_order(0));
+  assert_eq!(
+    optz.render_help(),
+    "Usage: test [--verbose] [--all] [--zap]\n\
+     \x20     --zap        Zap\n\
+     \x20     --verbose    Verbose\n\
+     \x20     --all        All\n\
+     \x20 -h, --help       Show help\n"
+  );
+  assert_eq!(
+    optz.sort_help(Sort::Alphabetical).render_help(),
+    "Usage: test [--verbose] [--all] [--zap]\n\
+     \x20     --zap        Zap\n\
+     \x20     --all        All\n\
+     \x20 -h, --help       Show help\n\
+     \x20     --verbose    Verbose\n"
+  );
+}
+
+#[test]
+fn test_long_help() {
+  let optz = Optz::from_args("test", vec!["test"]).option(
+    Opt::arg("level")
+      .description("Log level")
+      .long_description("Log level.\nOne of error, warn or info.")
+      .default_value("warn"),
+  );
+  assert_eq!(
+    optz.render_help(),
+    "Usage: test [--level <level>]\n\
+     \x20     --level <level>  Log level [default: warn]\n\
+     \x20 -h, --help           Show help\n"
+  );
+  assert_eq!(
+    optz.render_long_help(),
+    "Usage: test [--level <level>]\n\
+     \x20     --level <level>  Log level.\n\
+     \x20                      One of error, warn or info. [default: warn]\n\
+     \x20 -h, --help           Show help\n"
+  );
+}
+
+#[test]
+fn test_help_flag_configuration() {
+  let optz = Optz::from_args("test", vec!["test"]).option(
+    Opt::flag("human").short("-h").description("Human sizes"),
+  );
+  assert_eq!(
+    optz.render_help(),
+    "Usage: test [-h]\n\
+     \x20 -h, --human      Human sizes\n\
+     \x20     --help       Show help\n"
+  );
+
+  let optz =
+    Optz::from_args("test", vec!["test"]).disable_help_flag(true);
+  assert_eq!(optz.render_help(), "Usage: test\n");
+
+  let optz = Optz::from_args("test", vec!["test"])
+    .help_flag_names(&["-?", "--usage", "-H"]);
+  assert_eq!(
+    optz.render_help(),
+    "Usage: test\n\
+     \x20 -?, --usage      Show help\n"
+  );
+  let optz = optz.parse().unwrap();
+  assert!(!optz.is_present("help"));
+}
+
+#[test]
+fn test_help_short_circuits() {
+  let spec = || {
+    Optz::from_args("test", vec!["test", "--help", "--num-items"])
+      .return_help(true)
+      .version("1.0")
+      .option(Opt::arg("num-items").required(true))
+  };
+  if let Err(OptzError::DisplayHelp(text)) = spec().parse() {
+    assert_eq!(text, spec().render_long_help());
+  } else {
+    panic!("Unexpected result");
+  }
+
+  let mut optz = spec();
+  optz.args = vec!["-V".to_string()];
+  let result = optz.parse();
+  if let Err(OptzError::DisplayVersion(text)) = result {
+    assert_eq!(text, "test 1.0\n");
+  } else {
+    panic!("Unexpected result");
+  }
+}
+
+#[test]
+fn test_messages() {
+  let messages = Messages {
+    usage: "Uso:".to_string(),
+    arguments: "Argumentos:".to_string(),
+    options: "Opciones:".to_string(),
+    show_help: "Mostrar ayuda".to_string(),
+    default: "por defecto".to_string(),
+    ..Messages::default()
+  };
+  let optz = Optz::from_args("test", vec!["test"])
+    .messages(messages)
+    .option(Opt::positional("file"))
+    .option(
+      Opt::arg("level").description("Nivel").default_value("1"),
+    );
+  assert_eq!(
+    optz.render_help(),
+    "Uso: test [--level <level>] [<file>]\n\
+     \n\
+     Argumentos:\n\
+     \x20 <file>\n\
+     \n\
+     Opciones:\n\
+     \x20     --level <level>  Nivel [por defecto: 1]\n\
+     \x20 -h, --help           Mostrar ayuda\n"
+  );
+}
+
+#[test]
+fn test_error_messages() {
+  let messages = Messages {
+    missing_argument: "Falta un argumento".to_string(),
+    required: "se requiere {}".to_string(),
+    conflict: "{} no se puede usar con {}".to_string(),
+    ..Messages::default()
+  };
+  let spec = Optz::from_args("test", vec!["test"])
+    .messages(messages)
+    .option(Opt::arg("level").required(true))
+    .option(Opt::flag("json").conflicts_with("level"));
+  let error = |args: Vec<&str>| spec.parse_from(args).unwrap_err();
+  assert_eq!(error(vec!["test"]).to_string(), "se requiere --level");
+  assert_eq!(
+    error(vec!["test", "--level"]).to_string(),
+    "Falta un argumento"
+  );
+  assert_eq!(
+    error(vec!["test", "--json", "--level", "1"]).to_string(),
+    "--json no se puede usar con --level"
+  );
+}
+
+#[cfg(feature = "fluent")]
+#[test]
+fn test_fluent_messages() {
+  let messages = Messages::from_fluent(
+    "usage = Uso:\n\
+     show-help = Mostrar ayuda\n\
+     conflict = { $arg1 } y { $arg0 } no se pueden usar juntos\n",
+  )
+  .unwrap();
+  assert_eq!(messages.usage, "Uso:");
+  assert_eq!(messages.conflict, "{1} y {0} no se pueden usar juntos");
+  assert_eq!(messages.options, Messages::default().options);
+  let spec = Optz::from_args("test", vec!["test"])
+    .messages(messages)
+    .option(Opt::arg("level").description("Nivel"))
+    .option(
+      Opt::flag("json").description("JSON").conflicts_with("level"),
+    );
+  assert_eq!(
+    spec.render_help(),
+    "Uso: test [--level <level>] [--json]\n\
+     \x20     --level <level>  Nivel\n\
+     \x20     --json           JSON\n\
+     \x20 -h, --help           Mostrar ayuda\n"
+  );
+  assert_eq!(
+    spec
+      .parse_from(vec!["test", "--json", "--level", "1"])
+      .unwrap_err()
+      .to_string(),
+    "--level y --json no se pueden usar juntos"
+  );
+
+  assert!(Messages::from_fluent("usage = { $arg9 }\n").is_err());
+  assert!(Messages::from_fluent("usage\n").is_err());
+}
+
+#[test]
+fn test_matches_messages() {
+  let messages = Messages {
+    no_such_option: "no existe la opción '{}'".to_string(),
+    out_of_range: "{0} no cabe en {2} ({1})".to_string(),
+    ..Messages::default()
+  };
+  let matches = Optz::from_args("test", vec!["test"])
+    .messages(messages)
+    .option(Opt::arg("level"))
+    .parse_from(vec!["test", "--level", "300"])
+    .unwrap();
+  assert_eq!(
+    matches.get::<u8>("nivel").unwrap_err().to_string(),
+    "no existe la opción 'nivel'"
+  );
+  assert_eq!(
+    matches.get::<u8>("level").unwrap_err().to_string(),
+    "300 no cabe en u8 (--level)"
+  );
+}
+
+#[test]
+fn test_value_error_messages() {
+  let messages = Messages {
+    invalid_value: "{1}: valor {0} no válido ({2})".to_string(),
+    expected_one_of: "se esperaba {}".to_string(),
+    unknown_command: "comando desconocido '{}'".to_string(),
+    did_you_mean: ", ¿quiso decir {}?".to_string(),
+    or: "o".to_string(),
+    ..Messages::default()
+  };
+  let spec = Optz::from_args("test", vec!["test"])
+    .messages(messages)
+    .option(Opt::arg("mode").possible_values(&["fast", "slow"]))
+    .subcommand_required(true)
+    .subcommand(Optz::new("push"))
+    .subcommand(Optz::new("posh"));
+  let error = |args: Vec<&str>| spec.parse_from(args).unwrap_err();
+  assert_eq!(
+    error(vec!["test", "--mode", "x", "push"]).to_string(),
+    "--mode: valor x no válido (se esperaba fast, slow)"
+  );
+  assert_eq!(
+    error(vec!["test", "psh"]).to_string(),
+    "comando desconocido 'psh', ¿quiso decir 'posh' o 'push'?"
+  );
+}
+
+#[test]
+fn test_help_commands() {
+  let optz = Optz::from_args("git", vec!["git"])
+    .subcommand(Optz::new("commit").description("Record changes"))
+    .subcommand(Optz::new("push"));
+  assert_eq!(
+    optz.render_help(),
+    "Usage: git [<command>]\n\
+     \x20 -h, --help       Show help\n\
+     \n\
+     Commands:\n\
+     \x20 commit           Record changes\n\
+     \x20 push\n\
+     \x20 help             Show help for a command\n"
+  );
+}
+
+#[test]
+fn test_help_subcommand() {
+  let result =
+    Optz::from_args("git", vec!["git", "help", "remote", "add"])
+      .return_help(true)
+      .subcommand(
+        Optz::new("remote")
+          .subcommand(Optz::new("add").description("Add a remote")),
+      )
+      .parse();
+  if let Err(OptzError::DisplayHelp(help)) = result {
+    assert_eq!(
+      help,
+      "Usage: git remote add\n\
+       \n\
+       Add a remote\n\
+       \n\
+       \x20 -h, --help       Show help\n"
+    );
+  } else {
+    panic!("Unexpected result");
+  }
+}
+
+#[test]
+fn test_help_unknown_subcommand() {
+  let spec = Optz::new("git")
+    .return_help(true)
+    .subcommand(Optz::new("remote").subcommand(Optz::new("add")))
+    .subcommand(Optz::new("push"));
+  let direct = spec.parse_from(vec!["git", "pus"]);
+  let via_help = spec.parse_from(vec!["git", "help", "pus"]);
+  for result in [direct, via_help] {
+    if let Err(OptzError::UnknownOption(msg)) = result {
+      assert_eq!(msg, "unknown command 'pus', did you mean 'push'?");
+    } else {
+      panic!("Unexpected result");
+    }
+  }
+  let result = spec.parse_from(vec!["git", "help", "nope"]);
+  if let Err(OptzError::UnknownOption(msg)) = result {
+    assert_eq!(msg, "unknown command 'nope'");
+  } else {
+    panic!("Unexpected result");
+  }
+  let result = spec.parse_from(vec!["git", "help", "remote", "ad"]);
+  if let Err(OptzError::UnknownOption(msg)) = result {
+    assert_eq!(msg, "unknown command 'ad', did you mean 'add'?");
+  } else {
+    panic!("Unexpected result");
+  }
+}
+
+#[test]
+fn test_help_hidden_and_experimental_commands() {
+  let optz = Optz::from_args("app", vec!["app"])
+    .disable_help_flag(true)
+    .subcommand(Optz::new("debug").hidden(true))
+    .subcommand(
+      Optz::new("beta").description("Try it").experimental(true),
+    );
+  assert_eq!(
+    optz.render_help(),
+    "Usage: app [--enable-experimental] [<command>]\n\
+     \x20     --enable-experimental  Allow experimental commands\n\
+     \n\
+     Commands:\n\
+     \x20 beta             Try it [experimental]\n"
+  );
+}
+
+#[test]
+fn test_help_env() {
+  let optz = Optz::from_args("test", vec!["test"]).option(
+    Opt::arg("port")
+      .description("Port")
+      .env("MYAPP_PORT")
+      .default_value("80"),
+  );
+  assert_eq!(
+    testing::render_help(&optz),
+    "Usage: test [--port <port>]\n\
+     \x20     --port <port>  Port [env: MYAPP_PORT] [default: 80]\n\
+     \x20 -h, --help         Show help\n"
+  );
+}
+
+#[test]
+fn test_help_without_description() {
+  let optz = Optz::from_args("test", vec!["test"])
+    .option(Opt::flag("verbose").short("-v"))
+    .option(Opt::arg("output").description("Output file"));
+  assert_eq!(
+    testing::render_help(&optz),
+    "Usage: test [-v] [--output <output>]\n\
+     \x20 -v, --verbose\n\
+     \x20     --output <output>  Output file\n\
+     \x20 -h, --help             Show help\n"
+  );
+}
@@ -0,0 +1,69 @@
+use optz::{Group, Opt, Optz};
+
+#[test]
+fn test_lint_clean() {
+  let optz = Optz::from_args("test", vec!["test"])
+    .option(Opt::flag("verbose").description("Verbose").short("-v"));
+  assert!(optz.lint().is_empty());
+}
+
+#[test]
+fn test_lint_warnings() {
+  let optz = Optz::from_args("test", vec!["test"])
+    .option(Opt::flag("dry_run").description("Dry run").short("n"))
+    .option(
+      Opt::flag("color")
+        .description("Color")
+        .default_value("true"),
+    )
+    .option(Opt::arg("out"))
+    .group(Group::new("output").args(["json"]));
+  let warnings = optz
+    .lint()
+    .iter()
+    .map(|w| w.to_string())
+    .collect::<Vec<_>>();
+  assert_eq!(
+    warnings,
+    vec![
+      "--dry_run: name 'dry_run' is not lowercase kebab-case \
+       (e.g. 'dry-run')",
+      "--dry_run: short 'n' should be a dash and one character \
+       (e.g. '-n')",
+      "--dry_run: not referenced by any group or requirement",
+      "--color: flag defaults to \"true\" and can never be turned off; \
+       remove the default or invert the flag (e.g. --no-...)",
+      "--color: not referenced by any group or requirement",
+      "--out: missing description",
+      "--out: not referenced by any group or requirement",
+      "group 'output' references unknown option 'json'",
+    ]
+  );
+}
+
+#[test]
+fn test_lint_value_names_and_references() {
+  let optz = Optz::from_args("test", vec!["test"])
+    .option(Opt::arg("input").description("Input").value_name("path"))
+    .option(
+      Opt::arg("output").description("Output").value_name("FILE"),
+    )
+    .option(Opt::arg("level").description("Level"))
+    .option(Opt::flag("json").description("JSON").requires("output"))
+    .option(Opt::flag("quiet").description("Quiet"))
+    .group(Group::new("source").args(["input"]));
+  let warnings = optz
+    .lint()
+    .iter()
+    .map(|w| w.to_string())
+    .collect::<Vec<_>>();
+  assert_eq!(
+    warnings,
+    vec![
+      "--output: value name 'FILE' does not match the style of \
+       'path' used by --input",
+      "--level: not referenced by any group or requirement",
+      "--quiet: not referenced by any group or requirement",
+    ]
+  );
+}
@@ -1,6 +1,11 @@
-use optz::{Opt, Optz, OptzError};
+use optz::{
+  Matched, Matches, Metadata, Opt, Optz, OptzError, RestArg, Route,
+  ValueSource,
+};
 use std::sync::{LazyLock, Mutex};
 
+mod common;
+
 #[test]
 fn test_flag() {
   let optz = Optz::from_args(
@@ -11,7 +16,7 @@ fn test_flag() {
   .parse()
   .unwrap();
   let result: bool = optz.get("verbose").unwrap().unwrap();
-  assert_eq!(result, true);
+  assert!(result);
 }
 
 #[test]
@@ -41,7 +46,7 @@ fn test_short_option() {
   .parse()
   .unwrap();
   let result: bool = optz.get("verbose").unwrap().unwrap();
-  assert_eq!(result, true);
+  assert!(result);
 }
 
 #[test]
@@ -73,6 +78,21 @@ fn test_config() {
   assert_eq!(*retrieved, MyConfig { value: 42 });
 }
 
+#[test]
+fn test_default_from() {
+  struct MyConfig {
+    port: u16,
+  }
+  let optz = Optz::from_args("test", vec!["test", "--host", "a"])
+    .config(MyConfig { port: 8080 })
+    .option(Opt::arg("port").default_from(|c: &MyConfig| c.port.to_string()))
+    .option(Opt::arg("host").default_from(|_: &MyConfig| "b".to_string()))
+    .parse()
+    .unwrap();
+  assert_eq!(optz.get::<u16>("port").unwrap(), Some(8080));
+  assert_eq!(optz.get::<String>("host").unwrap(), Some("a".to_string()));
+}
+
 #[test]
 fn test_default_value() {
   let optz = Optz::from_args("test", vec!["test"])
@@ -108,8 +128,31 @@ fn test_unknown_option_ignored() {
 
 #[test]
 fn test_usage_default() {
-  let optz = Optz::new("myprog").parse().unwrap();
-  assert_eq!(optz.usage, Some("Usage: myprog [options]".to_string()));
+  let spec = Optz::new("myprog").into_shared();
+  assert_eq!(spec.usage, Some("Usage: myprog".to_string()));
+}
+
+#[test]
+fn test_usage_synopsis() {
+  let spec = Optz::new("prog")
+    .option(Opt::flag("verbose").short("-v"))
+    .option(Opt::arg("output").required(true))
+    .option(Opt::arg("include").short("-I").multiple(true))
+    .option(Opt::positional("input").many(1..))
+    .into_shared();
+  assert_eq!(
+    spec.usage.as_deref(),
+    Some(
+      "Usage: prog [-v] --output <output> [--include <include>]... \
+       [--] <input>..."
+    )
+  );
+
+  let spec = Optz::new("cargo-x").forward_to("cargo").into_shared();
+  assert_eq!(
+    spec.usage.as_deref(),
+    Some("Usage: cargo-x -- <args>...")
+  );
 }
 
 #[test]
@@ -215,5 +258,1961 @@ fn test_short_and_long_options() {
   .parse()
   .unwrap();
   let result: bool = optz.get("verbose").unwrap().unwrap();
-  assert_eq!(result, true);
+  assert!(result);
+}
+
+#[test]
+fn test_range() {
+  let optz = Optz::from_args("test", vec!["test", "--port", "8080"])
+    .option(Opt::arg("port").range(1..=65535))
+    .parse()
+    .unwrap();
+  let result: u16 = optz.get("port").unwrap().unwrap();
+  assert_eq!(result, 8080);
+}
+
+#[test]
+fn test_range_out_of_bounds() {
+  let result = Optz::from_args("test", vec!["test", "--port", "70000"])
+    .option(Opt::arg("port").range(1..=65535))
+    .parse();
+  if let Err(OptzError::InvalidValue(msg)) = result {
+    assert_eq!(msg, "value 70000 for --port is not in 1..=65535");
+  } else {
+    panic!("Unexpected result");
+  }
+}
+
+#[test]
+fn test_value_parser() {
+  let optz = Optz::from_args("test", vec!["test", "--point", "3,4"])
+    .option(Opt::arg("point").value_parser(|s| {
+      let (x, y) = s.split_once(',').ok_or("expected x,y")?;
+      Ok::<_, String>((
+        x.parse::<i32>().map_err(|e| e.to_string())?,
+        y.parse::<i32>().map_err(|e| e.to_string())?,
+      ))
+    }))
+    .parse()
+    .unwrap();
+  let point: &(i32, i32) = optz.get_one("point").unwrap();
+  assert_eq!(*point, (3, 4));
+}
+
+#[test]
+fn test_value_parser_error() {
+  let result = Optz::from_args("test", vec!["test", "--point", "3"])
+    .option(Opt::arg("point").value_parser(|s| {
+      s.split_once(',')
+        .map(|(x, y)| (x.to_string(), y.to_string()))
+        .ok_or_else(|| "expected x,y".to_string())
+    }))
+    .parse();
+  if let Err(OptzError::InvalidValue(msg)) = result {
+    assert_eq!(msg, "invalid value 3 for --point: expected x,y");
+  } else {
+    panic!("Unexpected result");
+  }
+}
+
+#[test]
+fn test_reparse_clone() {
+  let spec = Optz::from_args("test", vec!["test", "-v"])
+    .option(Opt::flag("verbose").short("-v"));
+  let prepared = spec.clone().into_shared();
+  let second = Optz::clone(&prepared).parse().unwrap();
+  let help_count =
+    second.options.iter().filter(|opt| opt.name == "help").count();
+  assert_eq!(help_count, 1);
+  assert!(second.has("verbose").unwrap());
+  assert_eq!(spec.options.len(), 1);
+}
+
+#[test]
+fn test_double_dash() {
+  let optz = Optz::from_args(
+    "test",
+    vec!["test", "--verbose", "--", "-f", "--verbose"],
+  )
+  .option(Opt::flag("verbose"))
+  .parse()
+  .unwrap();
+  assert_eq!(optz.rest, vec!["-f", "--verbose"]);
+}
+
+#[test]
+fn test_strict_unknown_option() {
+  let result = Optz::from_args("test", vec!["test", "-f"])
+    .strict(true)
+    .parse();
+  if let Err(OptzError::UnknownOption(msg)) = result {
+    assert_eq!(
+      msg,
+      "unknown option '-f'; to pass it as a positional argument, \
+       put it after '--' (e.g. '-- -f')"
+    );
+  } else {
+    panic!("Unexpected result");
+  }
+}
+
+#[test]
+fn test_flag_explicit_value() {
+  let optz = Optz::from_args(
+    "test",
+    vec!["test", "--verbose=false", "--color=on", "--cache=no"],
+  )
+  .option(Opt::flag("verbose"))
+  .option(Opt::flag("color"))
+  .option(Opt::flag("cache"))
+  .parse()
+  .unwrap();
+  assert!(!optz.has("verbose").unwrap());
+  assert!(optz.has("color").unwrap());
+  assert_eq!(optz.get::<bool>("cache").unwrap(), Some(false));
+}
+
+#[test]
+fn test_flag_false_is_not_present() {
+  let spec = Optz::from_args("test", vec!["test"])
+    .return_help(true)
+    .option(Opt::flag("quiet"))
+    .option(Opt::flag("verbose").conflicts_with("quiet"))
+    .option(Opt::flag("all").exclusive(true));
+  let optz = spec.parse_from(vec!["test", "--help=false"]).unwrap();
+  assert!(!optz.has("help").unwrap());
+  spec
+    .parse_from(vec!["test", "--quiet=false", "--verbose"])
+    .unwrap();
+  spec
+    .parse_from(vec!["test", "--all=no", "--verbose"])
+    .unwrap();
+  let result = spec.parse_from(vec!["test", "--quiet", "--verbose"]);
+  if let Err(OptzError::Conflict(msg)) = result {
+    assert_eq!(msg, "--verbose cannot be used with --quiet");
+  } else {
+    panic!("Unexpected result");
+  }
+}
+
+#[test]
+fn test_flag_invalid_explicit_value() {
+  let result = Optz::from_args("test", vec!["test", "--verbose=maybe"])
+    .option(Opt::flag("verbose"))
+    .parse();
+  if let Err(OptzError::InvalidValue(msg)) = result {
+    assert_eq!(
+      msg,
+      "invalid value maybe for --verbose: expected one of true, false, \
+       yes, no, on, off, 1, 0"
+    );
+  } else {
+    panic!("Unexpected result");
+  }
+}
+
+#[test]
+fn test_arg_attached_value() {
+  let optz =
+    Optz::from_args("test", vec!["test", "--num-items=12", "file"])
+      .option(Opt::arg("num-items"))
+      .parse()
+      .unwrap();
+  assert_eq!(optz.get::<u32>("num-items").unwrap(), Some(12));
+  assert_eq!(optz.rest, vec!["file"]);
+}
+
+#[test]
+fn test_possible_values() {
+  let result = Optz::from_args("test", vec!["test", "--format", "xml"])
+    .option(Opt::arg("format").possible_values(&["json", "yaml"]))
+    .parse();
+  if let Err(OptzError::InvalidValue(msg)) = result {
+    assert_eq!(
+      msg,
+      "invalid value xml for --format: expected one of json, yaml"
+    );
+  } else {
+    panic!("Unexpected result");
+  }
+}
+
+#[test]
+fn test_possible_values_prefix_match() {
+  let optz = Optz::from_args("test", vec!["test", "--format", "ya"])
+    .option(
+      Opt::arg("format")
+        .possible_values(&["json", "jsonl", "yaml"])
+        .prefix_match(true),
+    )
+    .parse()
+    .unwrap();
+  assert_eq!(optz.get::<String>("format").unwrap().unwrap(), "yaml");
+
+  let result = Optz::from_args("test", vec!["test", "--format", "js"])
+    .option(
+      Opt::arg("format")
+        .possible_values(&["json", "jsonl", "yaml"])
+        .prefix_match(true),
+    )
+    .parse();
+  if let Err(OptzError::InvalidValue(msg)) = result {
+    assert_eq!(
+      msg,
+      "invalid value js for --format: ambiguous, could be json, jsonl"
+    );
+  } else {
+    panic!("Unexpected result");
+  }
+}
+
+#[test]
+fn test_default_value_if() {
+  let spec = Optz::from_args("test", vec!["test", "--format", "json"])
+    .option(Opt::arg("format"))
+    .option(
+      Opt::arg("pretty")
+        .default_value("true")
+        .default_value_if("format", Some("json"), "false"),
+    )
+    .option(
+      Opt::arg("color")
+        .default_value("auto")
+        .default_value_if("format", None, "never"),
+    );
+  let optz = spec.clone().parse().unwrap();
+  assert_eq!(optz.get::<bool>("pretty").unwrap(), Some(false));
+  assert_eq!(optz.get::<String>("color").unwrap().unwrap(), "never");
+
+  let mut spec = spec;
+  spec.args = vec!["--format".to_string(), "yaml".to_string()];
+  let optz = spec.parse().unwrap();
+  assert_eq!(optz.get::<bool>("pretty").unwrap(), Some(true));
+  assert_eq!(optz.get::<String>("color").unwrap().unwrap(), "never");
+}
+
+#[test]
+fn test_default_value_if_sees_defaults() {
+  let spec = Optz::from_args("test", vec!["test"])
+    .option(Opt::arg("format").default_value("json"))
+    .option(Opt::arg("pretty").default_value_if(
+      "format",
+      Some("json"),
+      "false",
+    ));
+  let optz = spec.parse_from(vec!["test"]).unwrap();
+  assert_eq!(optz.get::<bool>("pretty").unwrap(), Some(false));
+  assert_eq!(optz.source_of("pretty"), Some(ValueSource::Default));
+  let optz =
+    spec.parse_from(vec!["test", "--format", "yaml"]).unwrap();
+  assert_eq!(optz.get::<bool>("pretty").unwrap(), None);
+}
+
+#[test]
+fn test_default_value_with_multiple() {
+  let optz = Optz::from_args("test", vec!["test", "--tag", "a"])
+    .option(Opt::arg("tag").multiple(true).default_value("none"))
+    .parse()
+    .unwrap();
+  assert_eq!(optz.get_values::<String>("tag").unwrap(), vec!["a"]);
+}
+
+#[test]
+fn test_default_value_fn() {
+  let optz = Optz::from_args("test", vec!["test"])
+    .option(Opt::arg("jobs").default_value_fn(|| (2 * 4).to_string()))
+    .parse()
+    .unwrap();
+  assert_eq!(optz.get::<u32>("jobs").unwrap(), Some(8));
+
+  let optz = Optz::from_args("test", vec!["test", "--jobs", "1"])
+    .option(Opt::arg("jobs").default_value_fn(|| unreachable!()))
+    .parse()
+    .unwrap();
+  assert_eq!(optz.get::<u32>("jobs").unwrap(), Some(1));
+}
+
+#[test]
+fn test_lenient_rest_args() {
+  let optz = Optz::from_args(
+    "test",
+    vec!["test", "file1", "--unknown", "-x", "--", "--also"],
+  )
+  .lenient(true)
+  .parse()
+  .unwrap();
+  assert_eq!(
+    optz.rest,
+    vec![
+      RestArg::Positional("file1".to_string()),
+      RestArg::UnknownOption("--unknown".to_string()),
+      RestArg::UnknownOption("-x".to_string()),
+      RestArg::Positional("--also".to_string()),
+    ]
+  );
+}
+
+#[test]
+fn test_get_bytes() {
+  let optz = Optz::from_args("test", vec!["test", "--key", "k\u{e9}y"])
+    .option(Opt::arg("key"))
+    .parse()
+    .unwrap();
+  assert_eq!(optz.get_bytes("key"), Some("k\u{e9}y".as_bytes()));
+  assert_eq!(optz.get_bytes("missing"), None);
+}
+
+#[cfg(unix)]
+#[test]
+fn test_get_bytes_os_args() {
+  use std::ffi::OsStr;
+  use std::os::unix::ffi::OsStrExt;
+  let args = [
+    OsStr::new("test"),
+    OsStr::from_bytes(b"--key=k\xffy"),
+    OsStr::new("sub"),
+    OsStr::new("--salt"),
+    OsStr::from_bytes(b"\x00\xfe"),
+  ];
+  let optz = Optz::from_args_os("test", args.to_vec())
+    .option(Opt::arg("key"))
+    .subcommand(Optz::new("sub").option(Opt::arg("salt")))
+    .parse()
+    .unwrap();
+  assert_eq!(optz.value_of("key"), Some("k\u{fffd}y"));
+  assert_eq!(optz.get_bytes("key"), Some(&b"k\xffy"[..]));
+  let (_, sub) = optz.subcommand().unwrap();
+  assert_eq!(sub.get_bytes("salt"), Some(&b"\x00\xfe"[..]));
+
+  let args = [
+    OsStr::new("test"),
+    OsStr::new("--a"),
+    OsStr::from_bytes(b"\xff"),
+    OsStr::new("sub"),
+    OsStr::new("--b"),
+    OsStr::from_bytes(b"\xfe"),
+  ];
+  let optz = Optz::from_args_os("test", args.to_vec())
+    .option(Opt::arg("a"))
+    .option(Opt::arg("b").global(true))
+    .subcommand(Optz::new("sub"))
+    .parse()
+    .unwrap();
+  assert_eq!(optz.get_bytes("a"), Some(&b"\xff"[..]));
+  assert_eq!(optz.get_bytes("b"), Some(&b"\xfe"[..]));
+  assert_eq!(optz.indices_of("b"), vec![5]);
+  let (_, sub) = optz.subcommand().unwrap();
+  assert_eq!(sub.get_bytes("b"), Some(&b"\xfe"[..]));
+}
+
+#[cfg(unix)]
+#[test]
+fn test_get_bytes_lossy_prefix() {
+  use std::ffi::OsStr;
+  use std::os::unix::ffi::OsStrExt;
+  fn remote(args: &[String]) -> Option<(usize, Vec<String>)> {
+    let (user, host) = args[0].split_once('@')?;
+    Some((
+      1,
+      vec![format!("--user={}", user), format!("--host={}", host)],
+    ))
+  }
+  for raw in [&b"\xffkey@host"[..], b"\xff\xff\xff\xff@y"] {
+    let args = [OsStr::new("test"), OsStr::from_bytes(raw)];
+    let optz = Optz::from_args_os("test", args.to_vec())
+      .tokenizer(remote)
+      .option(Opt::arg("user"))
+      .option(Opt::arg("host"))
+      .parse()
+      .unwrap();
+    let host = optz.value_of("host").unwrap();
+    assert_eq!(optz.get_bytes("host"), Some(host.as_bytes()));
+  }
+}
+
+#[test]
+fn test_batch_options() {
+  let plugins = ["alpha", "beta"];
+  let optz = Optz::from_args("test", vec!["test", "--beta"])
+    .options(plugins.iter().map(|name| Opt::flag(name)))
+    .parse()
+    .unwrap();
+  assert!(!optz.has("alpha").unwrap());
+  assert!(optz.has("beta").unwrap());
+}
+
+#[test]
+fn test_exit_code() {
+  let usage = Optz::from_args("test", vec!["test", "--num"])
+    .option(Opt::arg("num"))
+    .parse()
+    .unwrap_err();
+  assert_eq!(usage.exit_code(), 2);
+
+  let failure = Optz::from_args("test", vec!["test"])
+    .handler(|_| Err(OptzError::Parse("failed".to_string())))
+    .parse()
+    .unwrap_err();
+  assert_eq!(failure.exit_code(), 1);
+}
+
+#[test]
+fn test_exec_safe() {
+  let result =
+    Optz::from_args("test", vec!["test", "--env", "A=1\nB=2"])
+      .option(Opt::arg("env").exec_safe(true))
+      .parse();
+  if let Err(OptzError::InvalidValue(msg)) = result {
+    assert_eq!(
+      msg,
+      "value for --env contains a NUL byte or line break; refusing \
+       to pass it to a subprocess or environment"
+    );
+  } else {
+    panic!("Unexpected result");
+  }
+
+  let optz = Optz::from_args("test", vec!["test", "--env", "A=1"])
+    .option(Opt::arg("env").exec_safe(true))
+    .parse()
+    .unwrap();
+  assert_eq!(optz.get::<String>("env").unwrap().unwrap(), "A=1");
+}
+
+#[test]
+fn test_forward_to() {
+  let optz = Optz::from_args(
+    "test",
+    vec!["test", "-v", "--", "build", "--release"],
+  )
+  .option(Opt::flag("verbose").short("-v"))
+  .forward_to("cargo")
+  .parse()
+  .unwrap();
+  assert_eq!(optz.forwarded(), ["build", "--release"]);
+  assert!(optz.rest.is_empty());
+  let command = optz.forward_command().unwrap();
+  assert_eq!(command.get_program(), "cargo");
+  assert_eq!(command.get_args().collect::<Vec<_>>(), ["build", "--release"]);
+}
+
+#[test]
+fn test_forward_to_requires_args() {
+  let result = Optz::from_args("test", vec!["test", "--"])
+    .forward_to("cargo")
+    .parse();
+  if let Err(OptzError::MissingRequired(msg)) = result {
+    assert_eq!(msg, "expected arguments for cargo after '--'");
+  } else {
+    panic!("Unexpected result");
+  }
+}
+
+#[test]
+fn test_aliases() {
+  let optz = Optz::from_args("test", vec!["test", "--colour", "-c"])
+    .option(Opt::flag("color").alias("--colour"))
+    .option(Opt::flag("compact").short("-k").visible_short_alias("-c"))
+    .parse()
+    .unwrap();
+  assert!(optz.has("color").unwrap());
+  assert!(optz.has("compact").unwrap());
+}
+
+#[test]
+fn test_deprecated_still_parses() {
+  let optz = Optz::from_args("test", vec!["test", "--out", "a.txt"])
+    .option(Opt::arg("out").deprecated("use --output instead"))
+    .parse()
+    .unwrap();
+  assert_eq!(optz.get::<String>("out").unwrap().unwrap(), "a.txt");
+}
+
+#[test]
+fn test_positionals() {
+  let spec = Optz::from_args(
+    "test",
+    vec!["test", "in.txt", "-v", "out.txt", "extra"],
+  )
+  .option(Opt::positional("output").index(2))
+  .option(Opt::positional("input").index(1).required(true))
+  .option(Opt::flag("verbose").short("-v"));
+  let optz = spec.clone().parse().unwrap();
+  assert_eq!(optz.get::<String>("input").unwrap().unwrap(), "in.txt");
+  assert_eq!(optz.get::<String>("output").unwrap().unwrap(), "out.txt");
+  assert_eq!(optz.rest, vec!["extra"]);
+  assert_eq!(
+    spec.into_shared().usage,
+    Some("Usage: test [-v] <input> [<output>]".to_string())
+  );
+}
+
+#[test]
+fn test_missing_required_positional() {
+  let result = Optz::from_args("test", vec!["test"])
+    .option(Opt::positional("input").required(true))
+    .parse();
+  if let Err(OptzError::MissingRequired(msg)) = result {
+    assert_eq!(msg, "<input> is required");
+  } else {
+    panic!("Unexpected result");
+  }
+}
+
+#[test]
+fn test_metadata() {
+  let plugin = ("resize", "0.3.1", "Resize images");
+  let spec = Optz::from_args("host", vec!["host"])
+    .metadata(Metadata {
+      name: plugin.0.to_string(),
+      version: Some(plugin.1.to_string()),
+      description: Some(plugin.2.to_string()),
+    })
+    .into_shared();
+  assert_eq!(spec.parse_from(vec!["host"]).unwrap().name, "resize");
+  assert_eq!(spec.version.as_deref(), Some("0.3.1"));
+  assert_eq!(spec.description.as_deref(), Some("Resize images"));
+  assert_eq!(spec.usage.as_deref(), Some("Usage: resize"));
+}
+
+#[test]
+fn test_variadic_positional() {
+  let spec = Optz::from_args("test", vec!["test", "out", "a", "b", "c"])
+    .option(Opt::positional("dest"))
+    .option(Opt::positional("files").many(1..=3));
+  let optz = spec.clone().parse().unwrap();
+  assert_eq!(optz.get::<String>("dest").unwrap().unwrap(), "out");
+  assert_eq!(
+    optz.get_values::<String>("files").unwrap(),
+    vec!["a", "b", "c"]
+  );
+  assert!(optz.rest.is_empty());
+  assert_eq!(
+    spec.clone().into_shared().usage.as_deref(),
+    Some("Usage: test [<dest>] [--] <files>...")
+  );
+
+  let mut too_few = spec.clone();
+  too_few.args = vec!["out".to_string()];
+  if let Err(OptzError::MissingRequired(msg)) = too_few.parse() {
+    assert_eq!(msg, "<files> expects at least 1 value(s), got 0");
+  } else {
+    panic!("Unexpected result");
+  }
+
+  let mut too_many = spec;
+  too_many.args.push("d".to_string());
+  if let Err(OptzError::InvalidValue(msg)) = too_many.parse() {
+    assert_eq!(msg, "<files> expects at most 3 value(s), got 4");
+  } else {
+    panic!("Unexpected result");
+  }
+}
+
+#[test]
+fn test_typed_rest() {
+  let optz = Optz::from_args("test", vec!["test", "3", "1", "4"])
+    .option(Opt::positional("first"))
+    .parse()
+    .unwrap();
+  assert_eq!(optz.get_positional::<u8>("first").unwrap(), Some(3));
+  assert_eq!(optz.get_rest::<u32>().unwrap(), vec![1, 4]);
+
+  let optz = Optz::from_args("test", vec!["test", "1", "x"])
+    .parse()
+    .unwrap();
+  assert!(matches!(
+    optz.get_rest::<u32>(),
+    Err(OptzError::Parse(msg)) if msg.starts_with("invalid positional argument x")
+  ));
+}
+
+#[test]
+fn test_get_values_reports_failed_value() {
+  let optz = Optz::from_args(
+    "test",
+    vec!["test", "--num-items", "10", "--num-items", "x"],
+  )
+  .option(Opt::arg("num-items").multiple(true))
+  .parse()
+  .unwrap();
+  let result = optz.get_values::<u32>("num-items");
+  if let Err(OptzError::Parse(msg)) = result {
+    assert_eq!(
+      msg,
+      "invalid value x (#2) for --num-items: ParseIntError { kind: \
+       InvalidDigit }"
+    );
+  } else {
+    panic!("Unexpected result");
+  }
+}
+
+#[test]
+fn test_get_reports_invalid_value() {
+  let optz = Optz::from_args("test", vec!["test", "--num-items", "x"])
+    .option(Opt::arg("num-items"))
+    .parse()
+    .unwrap();
+  let result = optz.get::<u32>("num-items");
+  if let Err(OptzError::Parse(msg)) = result {
+    assert_eq!(
+      msg,
+      "invalid value x for --num-items: ParseIntError { kind: \
+       InvalidDigit }"
+    );
+  } else {
+    panic!("Unexpected result");
+  }
+
+  let optz = Optz::from_args("test", vec!["test", "--pin", "x"])
+    .option(Opt::arg("pin").secret(true))
+    .parse()
+    .unwrap();
+  if let Err(OptzError::Parse(msg)) = optz.get::<u32>("pin") {
+    assert!(msg.starts_with("invalid value *** for --pin: "));
+  } else {
+    panic!("Unexpected result");
+  }
+}
+
+#[test]
+fn test_numeric_conversion_errors() {
+  let optz = Optz::from_args(
+    "test",
+    vec!["test", "--count", "3.7", "--size", "99999999999"],
+  )
+  .option(Opt::arg("count"))
+  .option(Opt::arg("size"))
+  .parse()
+  .unwrap();
+  if let Err(OptzError::LossyConversion(msg)) =
+    optz.get::<u32>("count")
+  {
+    assert_eq!(
+      msg,
+      "value 3.7 for --count is not a whole number and cannot be \
+       converted to u32 without loss"
+    );
+  } else {
+    panic!("Unexpected result");
+  }
+  if let Err(OptzError::NumericOverflow(msg)) =
+    optz.get::<u32>("size")
+  {
+    assert_eq!(
+      msg,
+      "value 99999999999 for --size is out of range for u32"
+    );
+  } else {
+    panic!("Unexpected result");
+  }
+  assert_eq!(optz.get::<f64>("count").unwrap(), Some(3.7));
+  let get = |value: &str| {
+    Optz::from_args("test", vec!["test", "--n", value])
+      .option(Opt::arg("n"))
+      .parse()
+      .unwrap()
+      .get::<u32>("n")
+  };
+  if let Err(OptzError::NumericOverflow(msg)) = get("-1") {
+    assert_eq!(msg, "value -1 for --n is out of range for u32");
+  } else {
+    panic!("Unexpected result");
+  }
+  assert!(matches!(get(" 5"), Err(OptzError::Parse(_))));
+  assert!(matches!(get("5x"), Err(OptzError::Parse(_))));
+}
+
+#[test]
+fn test_is_present() {
+  let spec = Optz::new("test")
+    .option(Opt::flag("color"))
+    .option(Opt::flag("verbose"))
+    .option(Opt::arg("level").default_value("1"));
+  let optz = spec.parse_from(vec!["test", "--color"]).unwrap();
+  assert!(optz.is_present("color"));
+  assert!(!optz.is_present("verbose"));
+  assert!(!optz.is_present("level"));
+
+  let optz = spec
+    .parse_from(vec!["test", "--color=off", "--verbose=false"])
+    .unwrap();
+  assert!(!optz.is_present("color"));
+  assert!(!optz.has("color").unwrap());
+  assert!(!optz.is_present("verbose"));
+
+  let optz = Optz::from_args("test", vec!["test", "--verbose=false"])
+    .option(Opt::flag("verbose"))
+    .handler(|optz| {
+      assert!(!optz.is_present("verbose"));
+      Ok(())
+    });
+  optz.dispatch().unwrap();
+}
+
+#[test]
+fn test_colon_value_separator() {
+  let optz = Optz::from_args(
+    "test",
+    vec!["test", "--define:KEY=VAL", "/out:file.txt", "/verbose"],
+  )
+  .value_separator(':')
+  .slash_options(true)
+  .option(Opt::arg("define"))
+  .option(Opt::arg("out"))
+  .option(Opt::flag("verbose"))
+  .parse()
+  .unwrap();
+  assert_eq!(optz.get::<String>("define").unwrap().unwrap(), "KEY=VAL");
+  assert_eq!(optz.get::<String>("out").unwrap().unwrap(), "file.txt");
+  assert!(optz.has("verbose").unwrap());
+}
+
+#[test]
+fn test_slash_options_keep_paths() {
+  let optz = Optz::from_args(
+    "test",
+    vec!["test", "/usr/bin", "/out:a.txt", "/tmp/out:b"],
+  )
+  .value_separator(':')
+  .slash_options(true)
+  .option(Opt::arg("out"))
+  .parse()
+  .unwrap();
+  assert_eq!(optz.value_of("out"), Some("a.txt"));
+  assert_eq!(optz.rest, vec!["/usr/bin", "/tmp/out:b"]);
+}
+
+#[test]
+fn test_get_or_and_get_required() {
+  let optz = Optz::from_args("test", vec!["test", "--port", "80"])
+    .option(Opt::arg("port"))
+    .option(Opt::arg("host"))
+    .parse()
+    .unwrap();
+  assert_eq!(optz.get_or::<u16>("port", 8080).unwrap(), 80);
+  assert_eq!(
+    optz.get_or("host", "localhost".to_string()).unwrap(),
+    "localhost"
+  );
+  assert_eq!(optz.get_required::<u16>("port").unwrap(), 80);
+  if let Err(OptzError::MissingRequired(msg)) =
+    optz.get_required::<String>("host")
+  {
+    assert_eq!(msg, "--host is required");
+  } else {
+    panic!("Unexpected result");
+  }
+}
+
+#[test]
+fn test_get_unknown_option() {
+  let optz = Optz::from_args("test", vec!["test"])
+    .option(Opt::flag("verbose"))
+    .parse()
+    .unwrap();
+  assert_eq!(optz.get::<bool>("verbose").unwrap(), None);
+  if let Err(OptzError::NoSuchOption(msg)) = optz.get::<bool>("verbos")
+  {
+    assert_eq!(msg, "no option named 'verbos'");
+  } else {
+    panic!("Unexpected result");
+  }
+  assert!(optz.get_values::<String>("verbos").is_err());
+  assert!(optz.get_path("verbos").is_err());
+}
+
+#[test]
+fn test_occurrences_and_indices() {
+  let optz = Optz::from_args(
+    "test",
+    vec!["test", "-i", "a", "-v", "file", "-i=b", "-v", "-o", "c"],
+  )
+  .option(Opt::arg("input").short("-i").multiple(true))
+  .option(Opt::arg("output").short("-o"))
+  .option(Opt::flag("verbose").short("-v").multiple(true))
+  .option(Opt::positional("file"))
+  .parse()
+  .unwrap();
+  assert_eq!(optz.occurrences("input"), 2);
+  assert_eq!(optz.occurrences("verbose"), 2);
+  assert_eq!(optz.occurrences("missing"), 0);
+  assert_eq!(optz.indices_of("input"), vec![2, 5]);
+  assert_eq!(optz.indices_of("verbose"), vec![3, 6]);
+  assert_eq!(optz.indices_of("output"), vec![8]);
+  assert_eq!(optz.indices_of("file"), vec![4]);
+  assert!(optz.indices_of("input")[0] < optz.indices_of("output")[0]);
+}
+
+#[test]
+fn test_value_of_and_values_of() {
+  let optz = Optz::from_args(
+    "test",
+    vec!["test", "--tag", "a", "--tag", "b", "--name", "x"],
+  )
+  .option(Opt::arg("tag").multiple(true))
+  .option(Opt::arg("name"))
+  .parse()
+  .unwrap();
+  assert_eq!(optz.value_of("name"), Some("x"));
+  assert_eq!(optz.value_of("missing"), None);
+  assert_eq!(optz.values_of("tag").collect::<Vec<_>>(), vec!["a", "b"]);
+  assert_eq!(optz.values_of("missing").count(), 0);
+}
+
+#[test]
+fn test_source_of() {
+  let optz = Optz::from_args("test", vec!["test", "--port", "80"])
+    .option(Opt::arg("port"))
+    .option(Opt::arg("host").default_value("localhost"))
+    .option(Opt::arg("user"))
+    .parse()
+    .unwrap();
+  assert_eq!(optz.source_of("port"), Some(ValueSource::CommandLine));
+  assert_eq!(optz.source_of("host"), Some(ValueSource::Default));
+  assert_eq!(optz.source_of("user"), None);
+}
+
+#[test]
+fn test_matched_preserves_interleaving() {
+  let optz = Optz::from_args(
+    "find",
+    vec!["find", "a", "--name", "x", "b", "--print", "--", "c"],
+  )
+  .option(Opt::arg("name"))
+  .option(Opt::flag("print"))
+  .parse()
+  .unwrap();
+  let option = |name: &str, value: &str, index| Matched::Option {
+    name: name.to_string(),
+    value: value.to_string(),
+    index,
+  };
+  let positional = |value: &str, index| Matched::Positional {
+    value: value.to_string(),
+    index,
+  };
+  assert_eq!(
+    optz.matched(),
+    [
+      positional("a", 1),
+      option("name", "x", 2),
+      positional("b", 4),
+      option("print", "true", 5),
+      positional("c", 7),
+    ]
+  );
+}
+
+#[test]
+fn test_tokenizer() {
+  fn rts(args: &[String]) -> Option<(usize, Vec<String>)> {
+    if args[0] != "+RTS" {
+      return None;
+    }
+    let end = args.iter().position(|a| a == "-RTS")?;
+    let flags = args[1..end].iter().map(|a| format!("--rts={}", a));
+    Some((end + 1, flags.collect()))
+  }
+  fn remote(args: &[String]) -> Option<(usize, Vec<String>)> {
+    let (user, host) = args[0].split_once('@')?;
+    Some((
+      1,
+      vec![format!("--user={}", user), format!("--host={}", host)],
+    ))
+  }
+  let optz = Optz::from_args(
+    "test",
+    vec!["test", "+RTS", "-N4", "-A64m", "-RTS", "me@box:22", "file"],
+  )
+  .tokenizer(rts)
+  .tokenizer(remote)
+  .option(Opt::arg("rts").multiple(true))
+  .option(Opt::arg("user"))
+  .option(Opt::arg("host"))
+  .parse()
+  .unwrap();
+  assert_eq!(
+    optz.get_values::<String>("rts").unwrap(),
+    ["-N4", "-A64m"]
+  );
+  assert_eq!(optz.value_of("user"), Some("me"));
+  assert_eq!(optz.value_of("host"), Some("box:22"));
+  assert_eq!(optz.indices_of("host"), [5]);
+  assert_eq!(optz.rest, vec!["file"]);
+}
+
+#[test]
+fn test_rest_preserves_order() {
+  let optz = Optz::from_args("test", vec!["test", "c", "a", "b"])
+    .parse()
+    .unwrap();
+  assert_eq!(optz.rest, vec!["c", "a", "b"]);
+}
+
+#[test]
+fn test_rest_keeps_duplicates() {
+  let optz =
+    Optz::from_args("test", vec!["test", "a", "b", "a", "a"])
+      .parse()
+      .unwrap();
+  assert_eq!(optz.rest, vec!["a", "b", "a", "a"]);
+  assert_eq!(
+    optz.get_rest::<String>().unwrap(),
+    ["a", "b", "a", "a"]
+  );
+}
+
+#[test]
+fn test_rest_interleaved_with_options() {
+  let optz = Optz::from_args(
+    "test",
+    vec![
+      "test", "z", "--level", "1", "y", "-v", "x", "--", "-v", "w",
+    ],
+  )
+  .lenient(true)
+  .option(Opt::arg("level"))
+  .option(Opt::flag("verbose").short("-v"))
+  .parse()
+  .unwrap();
+  assert_eq!(optz.rest, vec!["z", "y", "x", "-v", "w"]);
+  assert_eq!(optz.occurrences("verbose"), 1);
+}
+
+#[test]
+fn test_rest_keeps_unknown_option_position() {
+  let optz =
+    Optz::from_args("test", vec!["test", "a", "--nope", "b"])
+      .lenient(true)
+      .parse()
+      .unwrap();
+  assert_eq!(
+    optz.rest,
+    vec![
+      RestArg::Positional("a".to_string()),
+      RestArg::UnknownOption("--nope".to_string()),
+      RestArg::Positional("b".to_string()),
+    ]
+  );
+}
+
+#[test]
+fn test_repeated_values_keep_order() {
+  let optz = Optz::from_args(
+    "test",
+    vec!["test", "-I", "b", "x", "-I", "a", "-I", "b"],
+  )
+  .option(Opt::arg("include").short("-I").multiple(true))
+  .parse()
+  .unwrap();
+  assert_eq!(
+    optz.get_values::<String>("include").unwrap(),
+    ["b", "a", "b"]
+  );
+  assert_eq!(optz.indices_of("include"), [2, 5, 7]);
+  assert_eq!(optz.rest, vec!["x"]);
+}
+
+#[test]
+fn test_secret_is_redacted() {
+  let optz = Optz::from_args(
+    "test",
+    vec!["test", "--password", "hunter2", "--token=s3cr3t", "-v"],
+  )
+  .option(Opt::arg("password").secret(true))
+  .option(Opt::arg("token").secret(true))
+  .option(Opt::flag("verbose").short("-v"))
+  .parse()
+  .unwrap();
+  assert_eq!(optz.value_of("password"), Some("hunter2"));
+  assert_eq!(optz.get::<String>("token").unwrap().unwrap(), "s3cr3t");
+  let debug = format!("{:?}", optz);
+  assert!(!debug.contains("hunter2"));
+  assert!(!debug.contains("s3cr3t"));
+  assert!(debug.contains("--token=***"));
+
+  let optz = Optz::from_args("test", vec!["test", "tok123"])
+    .option(Opt::positional("token").secret(true))
+    .parse()
+    .unwrap();
+  assert_eq!(optz.value_of("token"), Some("tok123"));
+  let debug = format!("{:?}", optz);
+  assert!(!debug.contains("tok123"));
+
+  let optz = Optz::from_args(
+    "test",
+    vec!["test", "login", "tok123", "--password", "hunter2"],
+  )
+  .subcommand(
+    Optz::new("login")
+      .option(Opt::positional("token").secret(true))
+      .option(Opt::arg("password").secret(true)),
+  )
+  .parse()
+  .unwrap();
+  let debug = format!("{:?}", optz);
+  assert!(!debug.contains("tok123"));
+  assert!(!debug.contains("hunter2"));
+
+  let result = Optz::from_args("test", vec!["test", "--pin", "12a4"])
+    .option(Opt::arg("pin").secret(true).range(0..=9999))
+    .parse();
+  if let Err(OptzError::InvalidValue(msg)) = result {
+    assert_eq!(msg, "value *** for --pin is not a number");
+  } else {
+    panic!("Unexpected result");
+  }
+
+  let error = |arg: Opt, value: &str| {
+    Optz::from_args("test", vec!["test", "--secret", value])
+      .expand_env(true)
+      .option(arg.secret(true))
+      .parse()
+      .unwrap_err()
+      .to_string()
+  };
+  assert_eq!(
+    error(Opt::arg("secret"), "hunter${2"),
+    "invalid value *** for --secret: unterminated '${'"
+  );
+  assert_eq!(
+    error(Opt::duration("secret"), "hunter2"),
+    "invalid value *** for --secret: invalid duration; expected e.g. \
+     30s, 5m or 1h30m (units: ms, s, m, h, d)"
+  );
+  let echo = optz::validators::from_fn(|value| {
+    Err(format!("'{}' is too short", value))
+  });
+  assert_eq!(
+    error(Opt::arg("secret").validator(echo), "hunter2"),
+    "invalid value *** for --secret: '***' is too short"
+  );
+}
+
+#[test]
+fn test_raw_args() {
+  let optz = Optz::from_args("test", vec!["./test", "--", "-v", "a"])
+    .option(Opt::flag("verbose").short("-v"))
+    .parse()
+    .unwrap();
+  assert_eq!(optz.raw_args(), ["./test", "--", "-v", "a"]);
+}
+
+#[test]
+fn test_redacted_invocation() {
+  let optz = Optz::from_args(
+    "deploy",
+    vec![
+      "deploy",
+      "--password",
+      "hunter2",
+      "--config=/home/me/app.toml",
+      "--name",
+      "it's me",
+      "~/notes.txt",
+      "-v",
+    ],
+  )
+  .option(Opt::arg("password").secret(true))
+  .option(Opt::arg("config"))
+  .option(Opt::arg("name"))
+  .option(Opt::flag("verbose").short("-v"))
+  .parse()
+  .unwrap();
+  assert_eq!(
+    optz.redacted_invocation(),
+    "deploy --password '***' '--config=<path>' --name 'it'\\''s me' \
+     '<path>' -v"
+  );
+}
+
+#[test]
+fn test_redacted_invocation_subcommand() {
+  let optz = Optz::from_args(
+    "prog",
+    vec!["prog", "login", "--password", "hunter2", "--token=s3cr3t"],
+  )
+  .option(Opt::arg("token").secret(true).global(true))
+  .subcommand(
+    Optz::new("login").option(Opt::arg("password").secret(true)),
+  )
+  .parse()
+  .unwrap();
+  assert_eq!(
+    optz.redacted_invocation(),
+    "prog login --password '***' '--token=***'"
+  );
+}
+
+#[test]
+fn test_require_equals() {
+  let optz =
+    Optz::from_args("test", vec!["test", "--level=3", "file"])
+      .option(Opt::arg("level").require_equals(true))
+      .parse()
+      .unwrap();
+  assert_eq!(optz.get::<u8>("level").unwrap(), Some(3));
+  assert_eq!(optz.rest, vec!["file"]);
+
+  let result = Optz::from_args("test", vec!["test", "--level", "3"])
+    .option(Opt::arg("level").require_equals(true))
+    .parse();
+  if let Err(OptzError::InvalidValue(msg)) = result {
+    assert_eq!(
+      msg,
+      "--level requires its value to be attached with '=', e.g. \
+       --level=<level>"
+    );
+  } else {
+    panic!("Unexpected result");
+  }
+}
+
+#[test]
+fn test_parse_from_leaves_spec_untouched() {
+  let spec = Optz::new("test")
+    .option(Opt::arg("level").default_value("1"))
+    .option(Opt::flag("verbose").short("-v"));
+  let first =
+    spec.parse_from(vec!["test", "--level", "3", "-v"]).unwrap();
+  let second = spec.parse_from(vec!["test", "a"]).unwrap();
+  assert_eq!(first.get::<u8>("level").unwrap(), Some(3));
+  assert!(first.is_present("verbose"));
+  assert_eq!(second.get::<u8>("level").unwrap(), Some(1));
+  assert!(!second.is_present("verbose"));
+  assert_eq!(second.rest, vec!["a"]);
+  assert_eq!(first.occurrences("verbose"), 1);
+  assert_eq!(
+    first.source_of("level"),
+    Some(ValueSource::CommandLine)
+  );
+  assert_eq!(second.source_of("level"), Some(ValueSource::Default));
+  assert_eq!(second.to_string(), "--level  1  default\nrest: a\n");
+  assert!(spec.options.iter().all(|opt| opt.values.is_empty()));
+}
+
+#[test]
+fn test_parse_from_skips_handlers() {
+  fn fail(_: &Optz) -> Result<(), OptzError> {
+    panic!("handler ran");
+  }
+  let spec = Optz::new("repl")
+    .version("1.0")
+    .arg_required_else_help(true)
+    .handler(fail)
+    .option(Opt::flag("run").handler(fail))
+    .option(Opt::arg("level"));
+  assert!(spec.parse_from(vec!["repl", "--run"]).is_ok());
+  assert!(matches!(
+    spec.parse_from(vec!["repl", "--help"]),
+    Err(OptzError::DisplayHelp(_))
+  ));
+  assert!(matches!(
+    spec.parse_from(vec!["repl", "--version"]),
+    Err(OptzError::DisplayVersion(_))
+  ));
+  assert!(matches!(
+    spec.parse_from(vec!["repl"]),
+    Err(OptzError::DisplayHelp(_))
+  ));
+}
+
+#[test]
+fn test_parse_from_threads() {
+  let spec = Optz::new("test").option(Opt::arg("n"));
+  std::thread::scope(|scope| {
+    for n in 0..4 {
+      let spec = &spec;
+      scope.spawn(move || {
+        let n = n.to_string();
+        let matches =
+          spec.parse_from(vec!["test", "--n", &n]).unwrap();
+        assert_eq!(matches.value_of("n"), Some(n.as_str()));
+      });
+    }
+  });
+}
+
+#[test]
+fn test_reparse_resets_values() {
+  let spec = Optz::new("test")
+    .option(Opt::arg("include").short("-I").multiple(true))
+    .into_shared();
+  spec.parse_from(vec!["test", "-I", "a"]).unwrap();
+  let optz = spec.parse_from(vec!["test", "-I", "a"]).unwrap();
+  assert_eq!(optz.get_values::<String>("include").unwrap(), ["a"]);
+  assert_eq!(optz.occurrences("include"), 1);
+}
+
+#[test]
+fn test_shared_spec() {
+  fn assert_send_sync<T: Send + Sync>() {}
+  assert_send_sync::<Optz>();
+  assert_send_sync::<Matches>();
+
+  let spec = Optz::new("test").option(Opt::arg("n")).into_shared();
+  let handles = (0..4)
+    .map(|n| {
+      let spec = spec.clone();
+      std::thread::spawn(move || {
+        let n = n.to_string();
+        let matches =
+          spec.parse_from(vec!["test", "--n", &n]).unwrap();
+        matches.get::<u8>("n").unwrap()
+      })
+    })
+    .collect::<Vec<_>>();
+  let results = handles
+    .into_iter()
+    .map(|h| h.join().unwrap())
+    .collect::<Vec<_>>();
+  assert_eq!(results, [Some(0), Some(1), Some(2), Some(3)]);
+  assert_eq!(spec.usage.as_deref(), Some("Usage: test [--n <n>]"));
+}
+
+#[test]
+fn test_shared_spec_help() {
+  let spec = Optz::new("test").option(Opt::arg("n")).into_shared();
+  let result = spec.parse_from(vec!["x", "--help"]);
+  assert!(matches!(result, Err(OptzError::DisplayHelp(_))));
+  let result = spec.parse_from(vec!["x", "--n", "1"]);
+  assert_eq!(result.unwrap().value_of("n"), Some("1"));
+}
+
+#[test]
+fn test_display_summary() {
+  let optz = Optz::from_args(
+    "test",
+    vec!["test", "-I", "a", "--token", "x", "-I", "b", "file"],
+  )
+  .option(Opt::arg("include").short("-I").multiple(true))
+  .option(Opt::arg("token").secret(true))
+  .option(Opt::arg("level").default_value("3"))
+  .option(Opt::flag("verbose"))
+  .parse()
+  .unwrap();
+  assert_eq!(
+    optz.to_string(),
+    "--include  a, b  command line\n\
+     --token    ***   command line\n\
+     --level    3     default\n\
+     rest: file\n"
+  );
+}
+
+#[test]
+fn test_renamed_from() {
+  let optz =
+    Optz::from_args("test", vec!["test", "--dry", "--colour=auto"])
+      .option(Opt::flag("dry-run").renamed_from("dry"))
+      .option(Opt::arg("color").renamed_from("colour"))
+      .parse()
+      .unwrap();
+  assert!(optz.is_present("dry-run"));
+  assert_eq!(optz.value_of("color"), Some("auto"));
+}
+
+#[test]
+fn test_crate_info() {
+  let optz = optz::crate_info!();
+  assert_eq!(optz.name, "optz");
+  assert_eq!(
+    optz.version.as_deref(),
+    Some(env!("CARGO_PKG_VERSION"))
+  );
+  assert_eq!(optz.authors, ["Josh Voigts <joshvoigts@gmail.com>"]);
+  assert_eq!(
+    optz.description.as_deref(),
+    Some("A no-magic option parser for Rust")
+  );
+}
+
+#[test]
+fn test_validate_only() {
+  fn fail(_: &Optz) -> Result<(), OptzError> {
+    panic!("handlers must not run");
+  }
+  let spec = Optz::new("test")
+    .handler(fail)
+    .option(Opt::arg("port").range(1..=65535).handler(fail))
+    .option(Opt::flag("help").handler(fail));
+  assert!(
+    spec
+      .validate_only(vec!["test", "--port", "80", "--help"])
+      .is_ok()
+  );
+  if let Err(OptzError::InvalidValue(msg)) =
+    spec.validate_only(vec!["test", "--port", "70000"])
+  {
+    assert_eq!(msg, "value 70000 for --port is not in 1..=65535");
+  } else {
+    panic!("Unexpected result");
+  }
+}
+
+#[test]
+fn test_subcommand() {
+  let optz = Optz::from_args("git", vec!["git"])
+    .option(Opt::flag("verbose").short("-v"))
+    .subcommand(
+      Optz::new("commit")
+        .description("Record changes")
+        .option(Opt::arg("message").short("-m")),
+    )
+    .subcommand(Optz::new("push"))
+    .parse_from(vec![
+      "git", "-v", "commit", "-m", "hello", "file.txt",
+    ])
+    .unwrap();
+  assert!(optz.get::<bool>("verbose").unwrap().unwrap());
+  let (name, sub) = optz.subcommand().unwrap();
+  assert_eq!(name, "commit");
+  assert_eq!(sub.value_of("message"), Some("hello"));
+  assert_eq!(sub.rest, vec![RestArg::Positional("file.txt".into())]);
+  assert!(optz.rest.is_empty());
+}
+
+#[test]
+fn test_subcommand_after_positional() {
+  let optz = Optz::from_args("test", vec!["test", "file", "push"])
+    .subcommand(Optz::new("push"))
+    .parse()
+    .unwrap();
+  assert!(optz.subcommand().is_none());
+  assert_eq!(
+    optz.rest,
+    vec![
+      RestArg::Positional("file".into()),
+      RestArg::Positional("push".into()),
+    ]
+  );
+}
+
+#[test]
+fn test_subcommand_fallback_to_positional() {
+  let optz = Optz::from_args("rg", vec!["rg", "pattern"])
+    .option(Opt::positional("pattern"))
+    .subcommand(Optz::new("search"))
+    .parse()
+    .unwrap();
+  assert!(optz.subcommand().is_none());
+  assert_eq!(optz.value_of("pattern"), Some("pattern"));
+}
+
+#[test]
+fn test_subcommand_strict_per_level() {
+  let spec = Optz::from_args("test", vec!["test"])
+    .lenient(true)
+    .subcommand(Optz::new("build").strict(true));
+  let optz = spec.parse_from(vec!["test", "-x", "build"]).unwrap();
+  assert_eq!(optz.rest, vec![RestArg::UnknownOption("-x".into())]);
+  assert!(optz.subcommand().is_some());
+  let result = spec.parse_from(vec!["test", "build", "-x"]);
+  if let Err(OptzError::UnknownOption(msg)) = result {
+    assert!(msg.starts_with("build: unknown option '-x'"));
+  } else {
+    panic!("Unexpected result");
+  }
+}
+
+#[test]
+fn test_subcommand_handlers() {
+  fn build(optz: &Optz) -> Result<(), OptzError> {
+    Err(OptzError::Parse(format!("ran {}", optz.name)))
+  }
+  let result = Optz::from_args("test", vec!["test", "build"])
+    .subcommand(Optz::new("build").handler(build))
+    .parse();
+  if let Err(OptzError::Parse(msg)) = result {
+    assert_eq!(msg, "ran build");
+  } else {
+    panic!("Unexpected result");
+  }
+}
+
+#[test]
+fn test_nested_subcommands() {
+  let spec = |args: Vec<&str>| {
+    Optz::from_args("git", args).subcommand(
+      Optz::new("remote").subcommand(
+        Optz::new("add")
+          .option(Opt::positional("url"))
+          .option(Opt::arg("port").range(1..=65535)),
+      ),
+    )
+  };
+  let optz =
+    spec(vec!["git", "remote", "add", "https://example.com"])
+      .parse()
+      .unwrap();
+  let (name, remote) = optz.subcommand().unwrap();
+  assert_eq!(name, "remote");
+  let (name, add) = remote.subcommand().unwrap();
+  assert_eq!(name, "add");
+  assert_eq!(add.full_name(), "git remote add");
+  assert_eq!(add.value_of("url"), Some("https://example.com"));
+  assert_eq!(
+    add.usage.as_deref(),
+    Some("Usage: git remote add [--port <port>] [<url>]")
+  );
+  let result =
+    spec(vec!["git", "remote", "add", "--port", "0"]).parse();
+  if let Err(OptzError::InvalidValue(msg)) = result {
+    assert_eq!(
+      msg,
+      "remote add: value 0 for --port is not in 1..=65535"
+    );
+  } else {
+    panic!("Unexpected result");
+  }
+}
+
+#[test]
+fn test_global_option() {
+  let spec = Optz::from_args("test", vec!["test"])
+    .option(Opt::flag("verbose").short("-v").global(true))
+    .option(Opt::arg("config").global(true))
+    .subcommand(Optz::new("build"));
+  let optz = spec
+    .parse_from(vec!["test", "-v", "build", "--config", "a.toml"])
+    .unwrap();
+  let (_, build) = optz.subcommand().unwrap();
+  assert!(build.get::<bool>("verbose").unwrap().unwrap());
+  assert_eq!(build.value_of("config"), Some("a.toml"));
+  assert_eq!(optz.value_of("config"), Some("a.toml"));
+  assert_eq!(
+    optz.source_of("config"),
+    Some(ValueSource::CommandLine)
+  );
+}
+
+#[test]
+fn test_global_option_constraints() {
+  let spec = Optz::from_args("t", vec!["t"])
+    .option(Opt::arg("config").global(true).required(true))
+    .option(Opt::flag("verbose").global(true))
+    .subcommand(
+      Optz::new("sub").option(Opt::flag("trace").requires("verbose")),
+    );
+  let optz =
+    spec.parse_from(vec!["t", "--config", "x", "sub"]).unwrap();
+  let (_, sub) = optz.subcommand().unwrap();
+  assert_eq!(sub.value_of("config"), Some("x"));
+  let optz =
+    spec.parse_from(vec!["t", "sub", "--config", "x"]).unwrap();
+  assert_eq!(optz.value_of("config"), Some("x"));
+  let optz = spec
+    .parse_from(vec![
+      "t",
+      "--config",
+      "x",
+      "--verbose",
+      "sub",
+      "--trace",
+    ])
+    .unwrap();
+  let (_, sub) = optz.subcommand().unwrap();
+  assert!(sub.is_present("trace"));
+  if let Err(OptzError::MissingRequired(msg)) =
+    spec.parse_from(vec!["t", "sub"])
+  {
+    assert_eq!(msg, "sub: --config is required");
+  } else {
+    panic!("Unexpected result");
+  }
+}
+
+#[test]
+fn test_subcommand_alias_and_inference() {
+  let spec = Optz::from_args("git", vec!["git"])
+    .infer_subcommands(true)
+    .subcommand(Optz::new("checkout").alias("co"))
+    .subcommand(Optz::new("cherry-pick"))
+    .subcommand(Optz::new("commit"));
+  let name = |args: Vec<&str>| {
+    let optz = spec.parse_from(args).unwrap();
+    optz.subcommand().map(|(name, _)| name.to_string())
+  };
+  assert_eq!(name(vec!["git", "co"]).as_deref(), Some("checkout"));
+  assert_eq!(name(vec!["git", "chec"]).as_deref(), Some("checkout"));
+  assert_eq!(name(vec!["git", "com"]).as_deref(), Some("commit"));
+  if let Err(OptzError::UnknownOption(msg)) =
+    spec.parse_from(vec!["git", "ch"])
+  {
+    assert_eq!(
+      msg,
+      "ambiguous command 'ch', could be checkout, cherry-pick"
+    );
+  } else {
+    panic!("Unexpected result");
+  }
+}
+
+#[test]
+fn test_subcommand_renamed_from() {
+  let optz = Optz::from_args("tool", vec!["tool", "fetch", "-q"])
+    .subcommand(
+      Optz::new("pull")
+        .renamed_from("fetch")
+        .option(Opt::flag("quiet").short("-q")),
+    )
+    .parse()
+    .unwrap();
+  let (name, pull) = optz.subcommand().unwrap();
+  assert_eq!(name, "pull");
+  assert!(pull.is_present("quiet"));
+}
+
+#[test]
+fn test_renamed_warning_once_per_parse() {
+  // Warnings go to stderr, so parse in a child of this test.
+  if std::env::var_os("OPTZ_TEST_RENAMED").is_some() {
+    Optz::from_args(
+      "tool",
+      vec!["tool", "--colour", "a", "--colour", "b", "fetch", "-q"],
+    )
+    .option(Opt::arg("color").renamed_from("colour").multiple(true))
+    .subcommand(
+      Optz::new("pull")
+        .renamed_from("fetch")
+        .option(Opt::flag("quiet").short("-q")),
+    )
+    .parse()
+    .unwrap();
+    return;
+  }
+  let output =
+    std::process::Command::new(std::env::current_exe().unwrap())
+      .args(["--exact", "test_renamed_warning_once_per_parse"])
+      .args(["--nocapture"])
+      .env("OPTZ_TEST_RENAMED", "1")
+      .output()
+      .unwrap();
+  assert!(output.status.success());
+  let stderr = String::from_utf8_lossy(&output.stderr);
+  let count = |warning: &str| stderr.matches(warning).count();
+  assert_eq!(
+    count("warning: --colour has been renamed to --color"),
+    1,
+    "{}",
+    stderr
+  );
+  assert_eq!(
+    count("warning: command fetch has been renamed to pull"),
+    1,
+    "{}",
+    stderr
+  );
+}
+
+#[test]
+fn test_dispatch() {
+  fn root(_: &Optz) -> Result<(), OptzError> {
+    panic!("only the matched subcommand's handler runs");
+  }
+  fn add(optz: &Optz) -> Result<(), OptzError> {
+    Err(OptzError::InvalidValue(format!(
+      "cannot add {}",
+      optz.value_of("url").unwrap()
+    )))
+  }
+  let result =
+    Optz::from_args("git", vec!["git", "remote", "add", "x"])
+      .handler(root)
+      .subcommand(Optz::new("remote").subcommand(
+        Optz::new("add").option(Opt::positional("url")).handler(add),
+      ))
+      .dispatch();
+  if let Err(OptzError::InvalidValue(msg)) = result {
+    assert_eq!(msg, "cannot add x");
+  } else {
+    panic!("Unexpected result");
+  }
+}
+
+#[test]
+fn test_run_dispatches() {
+  fn root(_: &Optz) -> Result<(), OptzError> {
+    panic!("only the matched subcommand's handler runs");
+  }
+  fn add(optz: &Optz) -> Result<(), OptzError> {
+    Err(OptzError::InvalidValue(format!(
+      "cannot add {}",
+      optz.value_of("url").unwrap()
+    )))
+  }
+  // `run` exits the process, so run it in a child of this test.
+  if std::env::var_os("OPTZ_TEST_RUN").is_some() {
+    optz::run(|| {
+      Optz::from_args("git", vec!["git", "remote", "add", "x"])
+        .handler(root)
+        .subcommand(
+          Optz::new("remote").subcommand(
+            Optz::new("add")
+              .option(Opt::positional("url"))
+              .handler(add),
+          ),
+        )
+    });
+  }
+  let output =
+    std::process::Command::new(std::env::current_exe().unwrap())
+      .args(["--exact", "test_run_dispatches", "--nocapture"])
+      .env("OPTZ_TEST_RUN", "1")
+      .output()
+      .unwrap();
+  assert_eq!(output.status.code(), Some(1));
+  let stderr = String::from_utf8_lossy(&output.stderr);
+  assert!(stderr.contains("git: cannot add x"), "{}", stderr);
+}
+
+#[test]
+fn test_route() {
+  fn add(_: &Optz) -> Result<(), OptzError> {
+    Ok(())
+  }
+  let spec = |args: Vec<&str>| {
+    Optz::from_args("git", args)
+      .subcommand(
+        Optz::new("remote")
+          .subcommand(Optz::new("add").handler(add))
+          .subcommand(Optz::new("show")),
+      )
+      .parse()
+      .unwrap()
+      .route()
+  };
+  assert_eq!(
+    spec(vec!["git", "remote", "add"]),
+    Route {
+      path: vec!["remote".to_string(), "add".to_string()],
+      handler_present: true,
+    }
+  );
+  let route = spec(vec!["git", "remote", "show"]);
+  assert_eq!(route.path, ["remote", "show"]);
+  assert!(!route.handler_present);
+  assert_eq!(spec(vec!["git"]), Route::default());
+}
+
+#[test]
+fn test_subcommand_required() {
+  let result = Optz::from_args("git", vec!["git", "-v"])
+    .option(Opt::flag("verbose").short("-v"))
+    .subcommand_required(true)
+    .subcommand(Optz::new("commit"))
+    .subcommand(Optz::new("push"))
+    .parse();
+  if let Err(OptzError::MissingRequired(msg)) = result {
+    assert_eq!(
+      msg,
+      "a command is required, expected one of commit, push\n\
+       Usage: git [-v] <command>"
+    );
+  } else {
+    panic!("Unexpected result");
+  }
+}
+
+#[test]
+fn test_subcommand_swallowed_by_option() {
+  let result =
+    Optz::from_args("mytool", vec!["mytool", "--config", "build"])
+      .option(Opt::arg("config"))
+      .subcommand_required(true)
+      .subcommand(Optz::new("build"))
+      .parse();
+  if let Err(OptzError::MissingRequired(msg)) = result {
+    assert_eq!(
+      msg,
+      "a command is required, expected one of build \
+       (--config took 'build' as its value)\n\
+       Usage: mytool [--config <config>] <command>"
+    );
+  } else {
+    panic!("Unexpected result");
+  }
+}
+
+#[test]
+fn test_default_subcommand() {
+  let spec = Optz::from_args("tool", vec!["tool"])
+    .arg_required_else_help(true)
+    .default_subcommand("run")
+    .subcommand(
+      Optz::new("run").option(Opt::arg("jobs").default_value("4")),
+    )
+    .subcommand(Optz::new("test"));
+  let optz = spec.parse_from(vec!["tool"]).unwrap();
+  let (name, run) = optz.subcommand().unwrap();
+  assert_eq!(name, "run");
+  assert_eq!(run.value_of("jobs"), Some("4"));
+  let optz = spec.parse_from(vec!["tool", "test"]).unwrap();
+  assert_eq!(optz.subcommand().unwrap().0, "test");
+}
+
+#[test]
+fn test_external_subcommand() {
+  let optz =
+    Optz::from_args("cargo", vec!["cargo", "-v", "fmt", "--check"])
+      .option(Opt::flag("verbose").short("-v"))
+      .allow_external_subcommands(true)
+      .subcommand_required(true)
+      .subcommand(Optz::new("build"))
+      .parse()
+      .unwrap();
+  assert!(optz.subcommand().is_none());
+  assert_eq!(
+    optz.external_subcommand(),
+    Some(("fmt", &["--check".to_string()][..]))
+  );
+  let command = optz.external_command().unwrap();
+  assert_eq!(command.get_program(), "cargo-fmt");
+  assert_eq!(command.get_args().collect::<Vec<_>>(), ["--check"]);
+}
+
+#[test]
+fn test_subcommand_suggestions() {
+  let spec = Optz::from_args("pkg", vec!["pkg"])
+    .subcommand(Optz::new("install"))
+    .subcommand(Optz::new("uninstall"))
+    .subcommand(Optz::new("list").alias("ls"));
+  let error = |args: Vec<&str>| match spec.parse_from(args) {
+    Err(OptzError::UnknownOption(msg)) => msg,
+    _ => panic!("Unexpected result"),
+  };
+  assert_eq!(
+    error(vec!["pkg", "instal"]),
+    "unknown command 'instal', did you mean 'install'?"
+  );
+  assert_eq!(
+    error(vec!["pkg", "ninstall"]),
+    "unknown command 'ninstall', did you mean 'install' or 'uninstall'?"
+  );
+  assert!(spec.parse_from(vec!["pkg", "upgrade"]).is_ok());
+  let required = spec.clone().subcommand_required(true);
+  if let Err(OptzError::UnknownOption(msg)) =
+    required.parse_from(vec!["pkg", "upgrade"])
+  {
+    assert_eq!(msg, "unknown command 'upgrade'");
+  } else {
+    panic!("Unexpected result");
+  }
+}
+
+#[test]
+fn test_multicall() {
+  let spec = |args: Vec<&str>| {
+    Optz::from_args("busybox", args)
+      .multicall(true)
+      .subcommand(
+        Optz::new("gzip").option(Opt::flag("keep").short("-k")),
+      )
+      .subcommand(Optz::new("gunzip"))
+  };
+  let optz =
+    spec(vec!["/usr/bin/gzip", "-k", "a.txt"]).parse().unwrap();
+  let (name, gzip) = optz.subcommand().unwrap();
+  assert_eq!(name, "gzip");
+  assert!(gzip.get::<bool>("keep").unwrap().unwrap());
+  assert_eq!(gzip.rest, vec![RestArg::Positional("a.txt".into())]);
+  assert_eq!(gzip.usage.as_deref(), Some("Usage: gzip [-k]"));
+  let optz = spec(vec!["busybox", "gunzip"]).parse().unwrap();
+  assert_eq!(optz.subcommand().unwrap().0, "gunzip");
+}
+
+#[test]
+fn test_propagate_version() {
+  let spec = Optz::from_args("prog", vec!["prog"])
+    .version("1.2.3")
+    .propagate_version(true)
+    .return_help(true)
+    .subcommand(Optz::new("remote").subcommand(Optz::new("add")))
+    .subcommand(Optz::new("plugin").version("0.1.0"));
+  let version = |args: Vec<&str>| match spec.parse_from(args) {
+    Err(OptzError::DisplayVersion(text)) => text,
+    _ => panic!("Unexpected result"),
+  };
+  assert_eq!(version(vec!["prog", "--version"]), "prog 1.2.3\n");
+  assert_eq!(
+    version(vec!["prog", "remote", "add", "-V"]),
+    "prog remote add 1.2.3\n"
+  );
+  assert_eq!(
+    version(vec!["prog", "plugin", "-V"]),
+    "prog plugin 0.1.0\n"
+  );
+}
+
+#[test]
+fn test_experimental_subcommand() {
+  let spec = Optz::from_args("my-app", vec!["my-app"])
+    .subcommand(Optz::new("beta").experimental(true));
+  if let Err(OptzError::Conflict(msg)) =
+    spec.parse_from(vec!["my-app", "beta"])
+  {
+    assert_eq!(
+      msg,
+      "'my-app beta' is experimental; pass --enable-experimental or \
+       set MY_APP_ENABLE_EXPERIMENTAL=1 to use it"
+    );
+  } else {
+    panic!("Unexpected result");
+  }
+  assert!(
+    spec
+      .parse_from(vec!["my-app", "--enable-experimental", "beta"])
+      .is_ok()
+  );
+  assert!(
+    spec
+      .parse_from(vec!["my-app", "beta", "--enable-experimental"])
+      .is_ok()
+  );
+  let _env = common::EnvGuard::set(&[
+    ("MY_APP_ENABLE_EXPERIMENTAL", "1"),
+  ]);
+  assert!(spec.parse_from(vec!["my-app", "beta"]).is_ok());
+}
+
+#[test]
+fn test_hidden_subcommand() {
+  let spec = Optz::from_args("test", vec!["test"])
+    .subcommand(Optz::new("debug").hidden(true));
+  let optz = spec.parse_from(vec!["test", "debug"]).unwrap();
+  assert_eq!(optz.subcommand().unwrap().0, "debug");
+  assert!(spec.parse_from(vec!["test", "debugg"]).is_ok());
+}
+
+#[test]
+fn test_env_fallback() {
+  let _env = common::EnvGuard::set(&[
+    ("OPTZ_TEST_PORT", "8080"),
+    ("OPTZ_TEST_DEBUG", "yes"),
+    ("OPTZ_TEST_EMPTY", ""),
+  ]);
+  let spec = Optz::from_args("test", vec!["test"])
+    .option(
+      Opt::arg("port").env("OPTZ_TEST_PORT").default_value("80"),
+    )
+    .option(Opt::flag("debug").env("OPTZ_TEST_DEBUG"))
+    .option(
+      Opt::arg("name").env("OPTZ_TEST_EMPTY").default_value("x"),
+    );
+  let optz = spec.parse_from(vec!["test"]).unwrap();
+  assert_eq!(optz.value_of("port"), Some("8080"));
+  assert_eq!(optz.source_of("port"), Some(ValueSource::Environment));
+  assert!(optz.has("debug").unwrap());
+  assert_eq!(optz.value_of("name"), Some("x"));
+  assert_eq!(optz.source_of("name"), Some(ValueSource::Default));
+  let optz = spec.parse_from(vec!["test", "--port", "9000"]).unwrap();
+  assert_eq!(optz.value_of("port"), Some("9000"));
+  assert_eq!(optz.source_of("port"), Some(ValueSource::CommandLine));
+}
+
+#[test]
+fn test_env_invalid_flag() {
+  let _env = common::EnvGuard::set(&[("OPTZ_TEST_FORCE", "maybe")]);
+  let result = Optz::from_args("test", vec!["test"])
+    .option(Opt::flag("force").env("OPTZ_TEST_FORCE"))
+    .parse();
+  if let Err(OptzError::InvalidValue(msg)) = result {
+    assert_eq!(
+      msg,
+      "invalid value maybe for --force: expected one of true, false, \
+       yes, no, on, off, 1, 0 (from OPTZ_TEST_FORCE)"
+    );
+  } else {
+    panic!("Unexpected result");
+  }
+}
+
+#[test]
+fn test_env_prefix() {
+  let _env = common::EnvGuard::set(&[
+    ("OPTZ_PREFIX_LOG_LEVEL", "debug"),
+    ("OPTZ_PREFIX_TOKEN", "secret"),
+    ("OPTZ_PREFIX_JOBS", "8"),
+    ("OPTZ_PREFIX_VERSION", "1"),
+  ]);
+  let optz = Optz::from_args("test", vec!["test", "build"])
+    .version("1.0.0")
+    .env_prefix("OPTZ_PREFIX")
+    .option(Opt::arg("log-level"))
+    .option(Opt::arg("token").skip_env(true))
+    .subcommand(Optz::new("build").option(Opt::arg("jobs")))
+    .parse()
+    .unwrap();
+  assert_eq!(optz.value_of("log-level"), Some("debug"));
+  assert_eq!(optz.value_of("token"), None);
+  let (_, build) = optz.subcommand().unwrap();
+  assert_eq!(build.value_of("jobs"), Some("8"));
+}
+
+#[test]
+fn test_env_prefix_shared() {
+  let _env = common::EnvGuard::set(&[
+    ("OPTZ_SHARED_VERSION", "1"),
+    ("OPTZ_SHARED_HELP", "1"),
+    ("OPTZ_SHARED_PORT", "8080"),
+  ]);
+  let spec = Optz::from_args("app", vec!["app"])
+    .version("1.0")
+    .return_help(true)
+    .env_prefix("OPTZ_SHARED")
+    .option(Opt::arg("port"))
+    .into_shared();
+  let optz = spec.parse_from(vec!["app"]).unwrap();
+  assert_eq!(optz.value_of("port"), Some("8080"));
+  assert!(!optz.is_present("version"));
+  assert_eq!(spec.render_env_docs(), "OPTZ_SHARED_PORT\n");
+}
+
+#[test]
+fn test_render_env_docs() {
+  let optz = Optz::from_args("test", vec!["test"])
+    .env_prefix("MYAPP")
+    .option(Opt::arg("port").description("Port to listen on"))
+    .option(Opt::flag("verbose").description("Enable verbose mode"))
+    .option(Opt::arg("token").skip_env(true))
+    .option(
+      Opt::arg("home").env("HOME").description("Home directory"),
+    )
+    .subcommand(Optz::new("build").option(Opt::arg("jobs")));
+  assert_eq!(
+    optz.render_env_docs(),
+    "MYAPP_PORT     Port to listen on\n\
+     MYAPP_VERBOSE  Enable verbose mode\n\
+     HOME           Home directory\n\
+     MYAPP_JOBS\n"
+  );
+  let optz = optz
+    .option(
+      Opt::arg("level")
+        .default_value("info")
+        .description("Log level"),
+    )
+    .option(Opt::arg("key").secret(true).default_value("k"));
+  assert_eq!(
+    optz.render_env_docs(),
+    "MYAPP_PORT           Port to listen on\n\
+     MYAPP_VERBOSE        Enable verbose mode\n\
+     HOME                 Home directory\n\
+     MYAPP_LEVEL    info  Log level\n\
+     MYAPP_KEY\n\
+     MYAPP_JOBS\n"
+  );
+  assert_eq!(
+    optz.render_env_docs_markdown(),
+    "| Variable | Default | Description |\n\
+     | --- | --- | --- |\n\
+     | `MYAPP_PORT` |  | Port to listen on |\n\
+     | `MYAPP_VERBOSE` |  | Enable verbose mode |\n\
+     | `HOME` |  | Home directory |\n\
+     | `MYAPP_LEVEL` | `info` | Log level |\n\
+     | `MYAPP_KEY` |  |  |\n\
+     | `MYAPP_JOBS` |  |  |\n"
+  );
 }
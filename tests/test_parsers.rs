@@ -0,0 +1,296 @@
+use optz::parsers::Url;
+use optz::{Opt, Optz, OptzError};
+use std::net::{IpAddr, SocketAddr};
+use std::path::PathBuf;
+use std::time::{Duration, UNIX_EPOCH};
+
+mod common;
+
+#[test]
+fn test_path() {
+  let optz =
+    Optz::from_args("test", vec!["test", "--out", "a/b.txt"])
+      .option(Opt::path("out"))
+      .parse()
+      .unwrap();
+  let path: &PathBuf = optz.get_one("out").unwrap();
+  assert_eq!(path, &PathBuf::from("a/b.txt"));
+}
+
+#[test]
+fn test_path_must_exist() {
+  let result =
+    Optz::from_args("test", vec!["test", "--in", "does/not/exist"])
+      .option(Opt::path("in").must_exist())
+      .parse();
+  if let Err(OptzError::InvalidValue(msg)) = result {
+    assert_eq!(
+      msg,
+      "invalid value does/not/exist for --in: path does not exist"
+    );
+  } else {
+    panic!("Unexpected result");
+  }
+
+  let optz =
+    Optz::from_args("test", vec!["test", "--in", "Cargo.toml"])
+      .option(Opt::path("in").must_exist())
+      .parse()
+      .unwrap();
+  assert!(optz.get_one::<PathBuf>("in").is_some());
+}
+
+#[test]
+fn test_ip_and_socket_addr() {
+  let optz = Optz::from_args(
+    "test",
+    vec!["test", "--ip", "::1", "--listen", "127.0.0.1:8080"],
+  )
+  .option(Opt::ip_addr("ip"))
+  .option(Opt::socket_addr("listen"))
+  .parse()
+  .unwrap();
+  let ip: &IpAddr = optz.get_one("ip").unwrap();
+  let listen: &SocketAddr = optz.get_one("listen").unwrap();
+  assert_eq!(ip, &"::1".parse::<IpAddr>().unwrap());
+  assert_eq!(listen.port(), 8080);
+
+  let result = Optz::from_args("test", vec!["test", "--ip", "1.2.3"])
+    .option(Opt::ip_addr("ip"))
+    .parse();
+  assert!(result.is_err());
+}
+
+#[test]
+fn test_url() {
+  let url: Url = "https://user@[::1]:8443/api?x=1".parse().unwrap();
+  assert_eq!(url.scheme, "https");
+  assert_eq!(url.host, "[::1]");
+  assert_eq!(url.port, Some(8443));
+  assert_eq!(url.path, "/api?x=1");
+  let url: Url = "http://[fe80::1]/".parse().unwrap();
+  assert_eq!((url.host.as_str(), url.port), ("[fe80::1]", None));
+
+  assert_eq!(
+    "http://::1".parse::<Url>(),
+    Err("IPv6 addresses must be in brackets (e.g. [::1])".to_string())
+  );
+  assert_eq!(
+    "http://[::g]:80".parse::<Url>(),
+    Err("invalid IPv6 address".to_string())
+  );
+
+  let result =
+    Optz::from_args("test", vec!["test", "--url", "example"])
+      .option(Opt::url("url"))
+      .parse();
+  if let Err(OptzError::InvalidValue(msg)) = result {
+    assert_eq!(
+      msg,
+      "invalid value example for --url: missing scheme (e.g. https://)"
+    );
+  } else {
+    panic!("Unexpected result");
+  }
+}
+
+#[test]
+fn test_get_path_and_socket_addr() {
+  let optz = Optz::from_args(
+    "test",
+    vec!["test", "--out", "out.txt", "--listen", "0.0.0.0:80"],
+  )
+  .option(Opt::arg("out"))
+  .option(Opt::socket_addr("listen"))
+  .option(Opt::arg("missing"))
+  .parse()
+  .unwrap();
+  assert_eq!(
+    optz.get_path("out").unwrap(),
+    Some(PathBuf::from("out.txt"))
+  );
+  assert_eq!(
+    optz.get_socket_addr("listen").unwrap().unwrap().port(),
+    80
+  );
+  assert_eq!(optz.get_path("missing").unwrap(), None);
+}
+
+#[test]
+fn test_get_socket_addr_error() {
+  let optz =
+    Optz::from_args("test", vec!["test", "--listen", "nope"])
+      .option(Opt::arg("listen"))
+      .parse()
+      .unwrap();
+  assert!(matches!(
+    optz.get_socket_addr("listen"),
+    Err(OptzError::InvalidValue(_))
+  ));
+}
+
+#[test]
+fn test_duration() {
+  let optz =
+    Optz::from_args("test", vec!["test", "--timeout", "2m30s"])
+      .option(Opt::duration("timeout"))
+      .parse()
+      .unwrap();
+  assert_eq!(
+    optz.get_duration("timeout").unwrap(),
+    Some(Duration::from_secs(150))
+  );
+  assert_eq!(
+    optz::parsers::duration("1h30m").unwrap(),
+    Duration::from_secs(5400)
+  );
+  assert_eq!(
+    optz::parsers::duration("250ms").unwrap(),
+    Duration::from_millis(250)
+  );
+}
+
+#[test]
+fn test_duration_error() {
+  let result =
+    Optz::from_args("test", vec!["test", "--timeout", "30"])
+      .option(Opt::duration("timeout"))
+      .parse();
+  if let Err(OptzError::InvalidValue(msg)) = result {
+    assert_eq!(
+      msg,
+      "invalid value 30 for --timeout: invalid duration; expected \
+       e.g. 30s, 5m or 1h30m (units: ms, s, m, h, d)"
+    );
+  } else {
+    panic!("Unexpected result");
+  }
+  assert!(optz::parsers::duration("5x").is_err());
+  assert!(optz::parsers::duration("m").is_err());
+}
+
+#[test]
+fn test_bytesize() {
+  let optz =
+    Optz::from_args("test", vec!["test", "--max-upload", "10MB"])
+      .option(Opt::arg("max-upload").bytes())
+      .parse()
+      .unwrap();
+  assert_eq!(
+    optz.get_bytesize("max-upload").unwrap(),
+    Some(10_000_000)
+  );
+  assert_eq!(*optz.get_one::<u64>("max-upload").unwrap(), 10_000_000);
+  assert_eq!(optz::parsers::bytesize("512K").unwrap(), 512 * 1024);
+  assert_eq!(optz::parsers::bytesize("1GiB").unwrap(), 1 << 30);
+  assert_eq!(optz::parsers::bytesize("42").unwrap(), 42);
+}
+
+#[test]
+fn test_bytesize_error() {
+  let err = optz::parsers::bytesize("10XB").unwrap_err();
+  assert_eq!(
+    err,
+    "invalid size; accepted suffixes: B, K, KB, KiB, M, MB, MiB, G, \
+     GB, GiB, T, TB, TiB"
+  );
+}
+
+#[test]
+fn test_timestamp() {
+  let optz = Optz::from_args(
+    "test",
+    vec!["test", "--since", "2024-01-01T00:00:00Z"],
+  )
+  .option(Opt::timestamp("since"))
+  .parse()
+  .unwrap();
+  let since = optz.get_timestamp("since").unwrap().unwrap();
+  assert_eq!(since, UNIX_EPOCH + Duration::from_secs(1_704_067_200));
+
+  let offset =
+    optz::parsers::timestamp("2024-01-01T02:30:00.5+02:30").unwrap();
+  assert_eq!(
+    offset,
+    UNIX_EPOCH + Duration::from_millis(1_704_067_200_500)
+  );
+  let before_epoch =
+    optz::parsers::timestamp("1969-12-31T23:59:59Z").unwrap();
+  assert_eq!(before_epoch, UNIX_EPOCH - Duration::from_secs(1));
+}
+
+#[test]
+fn test_timestamp_error() {
+  let err =
+    optz::parsers::timestamp("2024-02-30T00:00:00Z").unwrap_err();
+  assert_eq!(
+    err,
+    "invalid timestamp (day out of range); expected RFC 3339 like \
+     2024-01-01T00:00:00Z"
+  );
+  assert!(optz::parsers::timestamp("2024-01-01").is_err());
+  assert!(optz::parsers::timestamp("2024-01-01T00:00:00").is_err());
+  for offset in ["+-1:00", "-+1:00", "+01:-1", "+ 1:00"] {
+    let err = optz::parsers::timestamp(&format!(
+      "2024-01-01T00:00:00{}",
+      offset
+    ))
+    .unwrap_err();
+    assert!(err.contains("(malformed timezone offset)"), "{}", offset);
+  }
+}
+
+#[test]
+fn test_expand_env() {
+  let _env = common::EnvGuard::set(&[
+    ("OPTZ_TEST_DIR", "/tmp/$NESTED"),
+    ("OPTZ_TEST_NAME", "app"),
+  ]);
+  let optz = Optz::from_args(
+    "test",
+    vec![
+      "test",
+      "--dir",
+      "${OPTZ_TEST_DIR}/$OPTZ_TEST_NAME",
+      "--price",
+      "\\$5 and $",
+      "--regex",
+      "^a$OPTZ_TEST_NAME$",
+    ],
+  )
+  .expand_env(true)
+  .option(Opt::arg("dir"))
+  .option(Opt::arg("price"))
+  .option(Opt::arg("regex").literal(true))
+  .parse()
+  .unwrap();
+  assert_eq!(
+    optz.get::<String>("dir").unwrap().unwrap(),
+    "/tmp/$NESTED/app"
+  );
+  assert_eq!(optz.get::<String>("price").unwrap().unwrap(), "$5 and $");
+  assert_eq!(
+    optz.get::<String>("regex").unwrap().unwrap(),
+    "^a$OPTZ_TEST_NAME$"
+  );
+}
+
+#[test]
+fn test_expand_env_undefined() {
+  let result = Optz::from_args(
+    "test",
+    vec!["test", "--dir", "$OPTZ_TEST_UNDEFINED/x"],
+  )
+  .expand_env(true)
+  .option(Opt::arg("dir"))
+  .parse();
+  if let Err(OptzError::InvalidValue(msg)) = result {
+    assert_eq!(
+      msg,
+      "invalid value $OPTZ_TEST_UNDEFINED/x for --dir: undefined \
+       environment variable 'OPTZ_TEST_UNDEFINED'"
+    );
+  } else {
+    panic!("Unexpected result");
+  }
+}
@@ -0,0 +1,43 @@
+use optz::testing::{self, assert_help_matches};
+use optz::{ColorChoice, Opt, Optz};
+
+fn spec() -> Optz {
+  Optz::from_args("myapp", vec!["myapp"])
+    .option(
+      Opt::flag("verbose")
+        .description("Enable verbose mode")
+        .short("-v"),
+    )
+    .option(
+      Opt::arg("num-items").description("Number of items to process"),
+    )
+}
+
+#[test]
+fn test_assert_help_matches() {
+  assert_help_matches!(spec(), "tests/golden/help.txt");
+  assert_help_matches!(
+    spec().color(ColorChoice::Always),
+    "tests/golden/help.txt"
+  );
+}
+
+#[test]
+fn test_help_diff() {
+  let diff = testing::help_diff(
+    &spec().option(Opt::flag("quiet").description("Be quiet")),
+    "tests/golden/help.txt",
+  )
+  .unwrap();
+  assert_eq!(
+    diff,
+    "help output does not match tests/golden/help.txt:\n\
+     - Usage: myapp [-v] [--num-items <num-items>]\n\
+     + Usage: myapp [-v] [--num-items <num-items>] [--quiet]\n\
+     \x20   -v, --verbose                Enable verbose mode\n\
+     \x20       --num-items <num-items>  Number of items to process\n\
+     +       --quiet                  Be quiet\n\
+     \x20   -h, --help                   Show help\n\
+     (set OPTZ_UPDATE_GOLDEN=1 to accept the new output)\n"
+  );
+}
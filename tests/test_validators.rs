@@ -0,0 +1,70 @@
+use optz::validators::{self, all, any, not};
+use optz::{Opt, Optz, OptzError};
+
+#[test]
+fn test_port_validator() {
+  let result = Optz::from_args("test", vec!["test", "--port", "0"])
+    .option(Opt::arg("port").validator(validators::port()))
+    .parse();
+  if let Err(OptzError::InvalidValue(msg)) = result {
+    assert_eq!(
+      msg,
+      "invalid value 0 for --port: expected a port number between 1 \
+       and 65535"
+    );
+  } else {
+    panic!("Unexpected result");
+  }
+}
+
+#[test]
+fn test_combinators() {
+  let host_or_email =
+    any(vec![validators::hostname(), validators::email()]);
+  assert!(host_or_email("example.com").is_ok());
+  assert!(host_or_email("me@example.com").is_ok());
+  assert_eq!(
+    host_or_email("not valid").unwrap_err(),
+    "expected a hostname or expected an email address"
+  );
+  assert_eq!(any(vec![])("x").unwrap_err(), "no value is accepted");
+
+  let not_localhost = all(vec![
+    validators::hostname(),
+    not(
+      validators::from_fn(|s| {
+        if s == "localhost" {
+          Ok(())
+        } else {
+          Err(String::new())
+        }
+      }),
+      "localhost is not allowed",
+    ),
+  ]);
+  assert!(not_localhost("example.com").is_ok());
+  assert_eq!(
+    not_localhost("localhost").unwrap_err(),
+    "localhost is not allowed"
+  );
+  assert!(not_localhost("-bad-").is_err());
+}
+
+#[cfg(feature = "semver")]
+#[test]
+fn test_semver_validator() {
+  let semver = validators::semver();
+  assert!(semver("1.2.3").is_ok());
+  assert!(semver("1.0.0-alpha.1+build.5").is_ok());
+  assert!(semver("1.2").is_err());
+  assert!(semver("01.2.3").is_err());
+  assert!(semver("1.2.3-").is_err());
+}
+
+#[cfg(feature = "uuid")]
+#[test]
+fn test_uuid_validator() {
+  let uuid = validators::uuid();
+  assert!(uuid("123e4567-e89b-12d3-a456-426614174000").is_ok());
+  assert!(uuid("123e4567-e89b-12d3-a456").is_err());
+}